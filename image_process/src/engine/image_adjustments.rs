@@ -0,0 +1,275 @@
+use image::{imageops, DynamicImage, Rgba32FImage};
+use log::*;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+//Rec. 709 luma coefficients
+const LUMA: [f32; 3] = [0.2126, 0.7152, 0.0722];
+//Strongest channel gain of the white balance, at -100 / 100
+const WHITE_BALANCE_RANGE: f32 = 0.3;
+
+/// Steps of `ImageParameters::apply_filter`
+#[wasm_bindgen]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum Adjustment {
+    EXPOSURE,
+    WHITE_BALANCE,
+    HIGHLIGHTS_SHADOWS,
+    BRIGHTEN,
+    CONTRAST,
+    GAMMA,
+    SATURATION,
+    VIBRANCE,
+    HUE,
+    GRAYSCALE,
+    INVERT,
+    BLUR,
+    SHARPEN,
+}
+
+impl Adjustment {
+    /// Same relative order as the original brighten, blur, hue, grayscale, contrast, invert
+    pub const DEFAULT_ORDER: [Adjustment; 13] = [
+        Adjustment::EXPOSURE,
+        Adjustment::WHITE_BALANCE,
+        Adjustment::HIGHLIGHTS_SHADOWS,
+        Adjustment::BRIGHTEN,
+        Adjustment::BLUR,
+        Adjustment::SATURATION,
+        Adjustment::VIBRANCE,
+        Adjustment::HUE,
+        Adjustment::GRAYSCALE,
+        Adjustment::CONTRAST,
+        Adjustment::GAMMA,
+        Adjustment::INVERT,
+        Adjustment::SHARPEN,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::EXPOSURE => "EXPOSURE",
+            Self::WHITE_BALANCE => "WHITE_BALANCE",
+            Self::HIGHLIGHTS_SHADOWS => "HIGHLIGHTS_SHADOWS",
+            Self::BRIGHTEN => "BRIGHTEN",
+            Self::CONTRAST => "CONTRAST",
+            Self::GAMMA => "GAMMA",
+            Self::SATURATION => "SATURATION",
+            Self::VIBRANCE => "VIBRANCE",
+            Self::HUE => "HUE",
+            Self::GRAYSCALE => "GRAYSCALE",
+            Self::INVERT => "INVERT",
+            Self::BLUR => "BLUR",
+            Self::SHARPEN => "SHARPEN",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Adjustment> {
+        Adjustment::DEFAULT_ORDER
+            .iter()
+            .copied()
+            .find(|adjustment| adjustment.name().eq_ignore_ascii_case(name))
+    }
+
+    /// `order` first, then the missing adjustments in the default order, without duplicates
+    pub fn complete_order(order: &[Adjustment]) -> Vec<Adjustment> {
+        let mut complete: Vec<Adjustment> = Vec::with_capacity(Adjustment::DEFAULT_ORDER.len());
+        for adjustment in order.iter().chain(Adjustment::DEFAULT_ORDER.iter()) {
+            if !complete.contains(adjustment) {
+                complete.push(*adjustment);
+            }
+        }
+        complete
+    }
+}
+
+pub fn luma([r, g, b]: [f32; 3]) -> f32 {
+    r * LUMA[0] + g * LUMA[1] + b * LUMA[2]
+}
+
+/// sRGB encoded value to linear light, both in [0, 1]
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Float RGBA copy of the image, linearized when `linear` is set
+pub fn to_working(img: &DynamicImage, linear: bool) -> Rgba32FImage {
+    let mut buffer = img.to_rgba32f();
+    if linear {
+        map_pixels(&mut buffer, |rgb| rgb.map(srgb_to_linear));
+    }
+    buffer
+}
+
+/// Back to sRGB with the bit depth of `like` : 16 bits and float images keep their precision
+pub fn from_working(mut buffer: Rgba32FImage, like: &DynamicImage, linear: bool) -> DynamicImage {
+    if linear {
+        map_pixels(&mut buffer, |rgb| rgb.map(linear_to_srgb));
+    }
+    let img = DynamicImage::ImageRgba32F(buffer);
+    match like {
+        DynamicImage::ImageLuma16(_)
+        | DynamicImage::ImageLumaA16(_)
+        | DynamicImage::ImageRgb16(_)
+        | DynamicImage::ImageRgba16(_) => DynamicImage::ImageRgba16(img.to_rgba16()),
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => img,
+        _ => DynamicImage::ImageRgba8(img.to_rgba8()),
+    }
+}
+
+/// Apply `func` on the RGB values of every pixel, clamped to [0, 1], alpha is kept
+pub fn map_pixels<F>(buffer: &mut Rgba32FImage, func: F)
+where
+    F: Fn([f32; 3]) -> [f32; 3],
+{
+    for pixel in buffer.pixels_mut() {
+        let rgb = func([pixel[0], pixel[1], pixel[2]]);
+        for c in 0..3 {
+            pixel[c] = rgb[c].clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Apply a pixel function on a decoded image, in sRGB
+pub fn map_rgb<F>(img: DynamicImage, func: F) -> DynamicImage
+where
+    F: Fn([f32; 3]) -> [f32; 3],
+{
+    let mut buffer = to_working(&img, false);
+    map_pixels(&mut buffer, func);
+    from_working(buffer, &img, false)
+}
+
+/// Multiply the light by 2^`stops`, the sRGB values are linearized first
+pub fn exposure(stops: f32, linear: bool) -> impl Fn([f32; 3]) -> [f32; 3] {
+    let gain = 2f32.powf(stops);
+    move |rgb| {
+        if linear {
+            rgb.map(|v| v * gain)
+        } else {
+            rgb.map(|v| linear_to_srgb(srgb_to_linear(v) * gain))
+        }
+    }
+}
+
+/// Warm (> 0) or cool (< 0) the image with `temperature`, magenta (> 0) or green (< 0) with `tint`
+/// Both from -100 to 100
+pub fn white_balance(temperature: f32, tint: f32) -> impl Fn([f32; 3]) -> [f32; 3] {
+    let t = temperature.clamp(-100.0, 100.0) / 100.0 * WHITE_BALANCE_RANGE;
+    let m = tint.clamp(-100.0, 100.0) / 100.0 * WHITE_BALANCE_RANGE;
+    let gains = [1.0 + t + m / 2.0, 1.0 - m, 1.0 - t + m / 2.0];
+    move |[r, g, b]| [r * gains[0], g * gains[1], b * gains[2]]
+}
+
+/// Darken (< 0) or lighten (> 0) the bright areas with `highlights`, the dark areas with `shadows`
+/// Both from -100 to 100, the midtones are barely changed
+pub fn highlights_shadows(highlights: f32, shadows: f32) -> impl Fn([f32; 3]) -> [f32; 3] {
+    let highlights = highlights.clamp(-100.0, 100.0) / 200.0;
+    let shadows = shadows.clamp(-100.0, 100.0) / 200.0;
+    move |rgb| {
+        let l = luma(rgb);
+        let delta = highlights * l * l + shadows * (1.0 - l) * (1.0 - l);
+        rgb.map(|v| v + delta)
+    }
+}
+
+/// Add `value` / 255 to every channel, like `DynamicImage::brighten` on 8 bits images
+pub fn brighten(value: i32) -> impl Fn([f32; 3]) -> [f32; 3] {
+    let offset = value as f32 / 255.0;
+    move |rgb| rgb.map(|v| v + offset)
+}
+
+/// Same formula as `DynamicImage::adjust_contrast`, the alpha is not changed
+pub fn contrast(contrast: f32) -> impl Fn([f32; 3]) -> [f32; 3] {
+    let percent = ((100.0 + contrast) / 100.0).powi(2);
+    move |rgb| rgb.map(|v| (v - 0.5) * percent + 0.5)
+}
+
+/// Above 1 brightens the midtones, below 1 darkens them
+pub fn gamma(gamma: f32) -> impl Fn([f32; 3]) -> [f32; 3] {
+    let exponent = 1.0 / gamma.max(0.01);
+    move |rgb| rgb.map(|v| v.powf(exponent))
+}
+
+/// From -100 (grayscale) to 100 (twice as saturated)
+pub fn saturation(amount: f32) -> impl Fn([f32; 3]) -> [f32; 3] {
+    let factor = 1.0 + amount.clamp(-100.0, 100.0) / 100.0;
+    move |rgb| {
+        let l = luma(rgb);
+        rgb.map(|v| l + (v - l) * factor)
+    }
+}
+
+/// Saturation weighted toward the dull colors, the saturated ones are barely changed
+/// From -100 to 100
+pub fn vibrance(amount: f32) -> impl Fn([f32; 3]) -> [f32; 3] {
+    let amount = amount.clamp(-100.0, 100.0) / 100.0;
+    move |rgb| {
+        let max = rgb[0].max(rgb[1]).max(rgb[2]);
+        let min = rgb[0].min(rgb[1]).min(rgb[2]);
+        let factor = 1.0 + amount * (1.0 - (max - min));
+        let l = luma(rgb);
+        rgb.map(|v| l + (v - l) * factor)
+    }
+}
+
+/// Same matrix as `DynamicImage::huerotate`, `degrees` clockwise
+pub fn hue(degrees: i32) -> impl Fn([f32; 3]) -> [f32; 3] {
+    let (sin, cos) = (degrees as f32).to_radians().sin_cos();
+    let matrix = [
+        [
+            0.213 + cos * 0.787 - sin * 0.213,
+            0.715 - cos * 0.715 - sin * 0.715,
+            0.072 - cos * 0.072 + sin * 0.928,
+        ],
+        [
+            0.213 - cos * 0.213 + sin * 0.143,
+            0.715 + cos * 0.285 + sin * 0.140,
+            0.072 - cos * 0.072 - sin * 0.283,
+        ],
+        [
+            0.213 - cos * 0.213 - sin * 0.787,
+            0.715 - cos * 0.715 + sin * 0.715,
+            0.072 + cos * 0.928 + sin * 0.072,
+        ],
+    ];
+    move |[r, g, b]| matrix.map(|row| row[0] * r + row[1] * g + row[2] * b)
+}
+
+pub fn grayscale() -> impl Fn([f32; 3]) -> [f32; 3] {
+    |rgb| [luma(rgb); 3]
+}
+
+pub fn invert() -> impl Fn([f32; 3]) -> [f32; 3] {
+    |rgb| rgb.map(|v| 1.0 - v)
+}
+
+/// Gaussian blur of every channel, alpha included
+pub fn blur(buffer: &Rgba32FImage, sigma: f32) -> Rgba32FImage {
+    imageops::blur(buffer, sigma)
+}
+
+/// Unsharp mask : add `amount` times the details removed by a small blur
+pub fn sharpen(buffer: &Rgba32FImage, amount: f32) -> Rgba32FImage {
+    let blurred = imageops::blur(buffer, 1.0);
+    let mut sharpened = buffer.clone();
+    for (pixel, soft) in sharpened.pixels_mut().zip(blurred.pixels()) {
+        for c in 0..3 {
+            pixel[c] = (pixel[c] + amount * (pixel[c] - soft[c])).clamp(0.0, 1.0);
+        }
+    }
+    trace!("Sharpen applied : {}", amount);
+    sharpened
+}
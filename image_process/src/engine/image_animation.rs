@@ -0,0 +1,535 @@
+use image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use image::codecs::png::PngDecoder;
+use image::{
+    imageops, AnimationDecoder, Delay, DynamicImage, Frame, GenericImageView, ImageFormat,
+    ImageResult, RgbaImage,
+};
+use log::*;
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+
+use super::image_metadata::{
+    self, png_chunks, riff_chunks, riff_chunks_from, u24_le, webp_chunk_header,
+};
+use super::{image_filters::ColorRgba, DecodeLimits, ErrorCode, ErrorStage, ProcessingError};
+
+//Used when a frame has no delay, like most browsers do
+const DEFAULT_DELAY_MS: u32 = 100;
+
+/// Frame of an animation, already composited on the whole canvas
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    pub image: DynamicImage,
+    pub delay_ms: u32,
+}
+
+/// Every frame of an animated GIF, APNG or WebP
+#[derive(Debug, Clone)]
+pub struct Animation {
+    frames: Vec<AnimationFrame>,
+    //Number of times the animation is played, 0 to loop forever
+    plays: u32,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<AnimationFrame>, plays: u32) -> Result<Animation, ProcessingError> {
+        let (width, height) = match frames.first() {
+            Some(frame) => frame.image.dimensions(),
+            None => return Err(ErrorCode::ImageEmpty.into()),
+        };
+        if let Some(index) = frames
+            .iter()
+            .position(|frame| frame.image.dimensions() != (width, height))
+        {
+            error!("Frame {} does not have the size of the first frame", index);
+            return Err(ProcessingError::new(ErrorCode::InvalidParsing, ErrorStage::PROCESS)
+                .with_cause("frames of different sizes")
+                .with_detail("frame", index));
+        }
+        Ok(Animation { frames, plays })
+    }
+
+    /// Decode all the frames with their delays, disposal and blending applied
+    /// `limits` is only used by WebP, each frame is checked before being decoded
+    pub fn decode(
+        format: ImageFormat,
+        bytes: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<Animation, ProcessingError> {
+        trace!("Decode {:?} animation", format);
+        let frames = match format {
+            ImageFormat::Gif => collect(GifDecoder::new(Cursor::new(bytes)))?,
+            ImageFormat::Png => collect(PngDecoder::new(Cursor::new(bytes)).map(|png| png.apng()))?,
+            ImageFormat::WebP => webp_frames(bytes, limits, usize::MAX)?,
+            _ => {
+                return Err(ProcessingError::new(ErrorCode::NotImplemented, ErrorStage::DECODE)
+                    .with_cause("format without animation")
+                    .with_detail("format", format!("{:?}", format)))
+            }
+        };
+        Animation::new(frames, plays(format, bytes))
+    }
+
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    pub fn into_frames(self) -> Vec<AnimationFrame> {
+        self.frames
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.frames.len() as u32
+    }
+
+    pub fn plays(&self) -> u32 {
+        self.plays
+    }
+
+    pub fn frame(&self, index: u32) -> Result<&AnimationFrame, ProcessingError> {
+        self.frames.get(index as usize).ok_or_else(|| {
+            error!("No frame {} in an animation of {}", index, self.frames.len());
+            ProcessingError::from(ErrorCode::FrameNotFound)
+                .with_detail("index", index)
+                .with_detail("frame_count", self.frames.len())
+        })
+    }
+
+    /// Apply the same processing on every frame, the delays are kept
+    pub fn map_frames<F>(&self, func: F) -> Result<Animation, ProcessingError>
+    where
+        F: Fn(DynamicImage) -> Result<DynamicImage, ProcessingError>,
+    {
+        info!("Process {} frames", self.frames.len());
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| {
+                Ok(AnimationFrame {
+                    image: func(frame.image.clone())?,
+                    delay_ms: frame.delay_ms,
+                })
+            })
+            .collect::<Result<Vec<_>, ProcessingError>>()?;
+        Animation::new(frames, self.plays)
+    }
+
+    /// Encode as an animated GIF
+    pub fn encode_gif(&self) -> Result<Vec<u8>, ProcessingError> {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut bytes);
+            //Without the NETSCAPE extension, a GIF is played once
+            let repeat = match self.plays {
+                0 => Some(Repeat::Infinite),
+                1 => None,
+                plays => Some(Repeat::Finite((plays - 1).min(u16::MAX as u32) as u16)),
+            };
+            if let Some(repeat) = repeat {
+                encoder.set_repeat(repeat).map_err(encode_error)?;
+            }
+            encoder
+                .encode_frames(self.frames.iter().map(|frame| {
+                    Frame::from_parts(
+                        frame.image.to_rgba8(),
+                        0,
+                        0,
+                        Delay::from_numer_denom_ms(frame.delay_ms, 1),
+                    )
+                }))
+                .map_err(encode_error)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Encode as an animated PNG, read as a still PNG by the viewers without APNG support
+    pub fn encode_apng(&self) -> Result<Vec<u8>, ProcessingError> {
+        let (width, height) = self.frames[0].image.dimensions();
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, width, height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder
+                .set_animated(self.frame_count(), self.plays)
+                .map_err(encode_error)?;
+            let mut writer = encoder.write_header().map_err(encode_error)?;
+            for frame in &self.frames {
+                writer
+                    .set_frame_delay(frame.delay_ms.min(u16::MAX as u32) as u16, 1000)
+                    .map_err(encode_error)?;
+                writer
+                    .write_image_data(frame.image.to_rgba8().as_raw())
+                    .map_err(encode_error)?;
+            }
+            writer.finish().map_err(encode_error)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Every frame in a grid, left to right then top to bottom
+    /// The sheet is checked against `limits` like a decoded image
+    pub fn contact_sheet(
+        &self,
+        options: &ContactSheetOptions,
+        limits: &DecodeLimits,
+    ) -> Result<DynamicImage, ProcessingError> {
+        let count = self.frames.len() as u32;
+        let columns = match options.columns {
+            0 => (count as f64).sqrt().ceil() as u32,
+            columns => columns.min(count),
+        }
+        .max(1);
+        let rows = count.div_ceil(columns);
+
+        let (width, height) = self.frames[0].image.dimensions();
+        let (cell_w, cell_h) = match options.thumbnail_width {
+            0 => (width as u64, height as u64),
+            thumbnail_width => (
+                thumbnail_width as u64,
+                ((height as u64 * thumbnail_width as u64) / width as u64).max(1),
+            ),
+        };
+        let spacing = options.spacing;
+        let (sheet_w, sheet_h) = limits.check_canvas(
+            sheet_side(columns, cell_w, spacing),
+            sheet_side(rows, cell_h, spacing),
+        )?;
+        //Both fit in the sheet, so in an u32
+        let (cell_w, cell_h) = (cell_w as u32, cell_h as u32);
+        info!(
+            "Contact sheet of {} frames : {}x{} cells of {}x{}",
+            count, columns, rows, cell_w, cell_h
+        );
+
+        let mut sheet = RgbaImage::from_pixel(sheet_w, sheet_h, options.background.into());
+        for (index, frame) in self.frames.iter().enumerate() {
+            let (column, row) = (index as u32 % columns, index as u32 / columns);
+            let cell = if (cell_w, cell_h) == (width, height) {
+                frame.image.to_rgba8()
+            } else {
+                imageops::resize(
+                    &frame.image.to_rgba8(),
+                    cell_w,
+                    cell_h,
+                    imageops::FilterType::Triangle,
+                )
+            };
+            imageops::overlay(
+                &mut sheet,
+                &cell,
+                (spacing + column * (cell_w + spacing)) as i64,
+                (spacing + row * (cell_h + spacing)) as i64,
+            );
+        }
+        Ok(DynamicImage::ImageRgba8(sheet))
+    }
+}
+
+//Cells and the spacing around them, saturated : the limits reject it anyway
+fn sheet_side(cells: u32, cell: u64, spacing: u32) -> u64 {
+    (cells as u64)
+        .saturating_mul(cell)
+        .saturating_add((cells as u64 + 1) * spacing as u64)
+}
+
+/// Layout of the frames extracted by `contact_sheet`
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone)]
+pub struct ContactSheetOptions {
+    //0 for a square grid
+    pub columns: u32,
+    //Width of each frame in the sheet, 0 to keep the frame size
+    pub thumbnail_width: u32,
+    //Pixels around and between the frames
+    pub spacing: u32,
+    pub background: ColorRgba,
+}
+
+#[wasm_bindgen]
+impl ContactSheetOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        columns: Option<u32>,
+        thumbnail_width: Option<u32>,
+        spacing: Option<u32>,
+        background: Option<ColorRgba>,
+    ) -> ContactSheetOptions {
+        let default = ContactSheetOptions::default();
+        ContactSheetOptions {
+            columns: columns.unwrap_or(default.columns),
+            thumbnail_width: thumbnail_width.unwrap_or(default.thumbnail_width),
+            spacing: spacing.unwrap_or(default.spacing),
+            background: background.unwrap_or(default.background),
+        }
+    }
+}
+
+impl Default for ContactSheetOptions {
+    fn default() -> Self {
+        Self {
+            columns: 0,
+            thumbnail_width: 0,
+            spacing: 4,
+            background: ColorRgba::new(255, 255, 255, 255),
+        }
+    }
+}
+
+/// The image has more than one frame and its format can hold an animation
+pub fn is_animated(format: ImageFormat, bytes: &[u8]) -> bool {
+    matches!(format, ImageFormat::Gif | ImageFormat::Png | ImageFormat::WebP)
+        && image_metadata::frame_count(format, bytes) > 1
+}
+
+fn encode_error<E: std::fmt::Display>(e: E) -> ProcessingError {
+    error!("Unable to encode the animation : {}", e);
+    ProcessingError::from(ErrorCode::UnableToSave)
+        .with_cause(e)
+        .with_detail("animated", true)
+}
+
+fn decode_error<E: std::fmt::Display>(e: E) -> ProcessingError {
+    error!("Unable to decode the animation : {}", e);
+    ProcessingError::from(ErrorCode::UnableToDecode)
+        .with_cause(e)
+        .with_detail("animated", true)
+}
+
+//Frames of the GIF / APNG decoders of the image crate
+fn collect<'a, D: AnimationDecoder<'a>>(
+    decoder: ImageResult<D>,
+) -> Result<Vec<AnimationFrame>, ProcessingError> {
+    let frames = decoder
+        .and_then(|decoder| decoder.into_frames().collect_frames())
+        .map_err(decode_error)?;
+    Ok(frames
+        .into_iter()
+        .map(|frame| {
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            let delay_ms = numerator / denominator.max(1);
+            AnimationFrame {
+                image: DynamicImage::ImageRgba8(frame.into_buffer()),
+                delay_ms: if delay_ms == 0 { DEFAULT_DELAY_MS } else { delay_ms },
+            }
+        })
+        .collect())
+}
+
+/// Number of plays of the animation, 0 to loop forever
+fn plays(format: ImageFormat, bytes: &[u8]) -> u32 {
+    match format {
+        //NETSCAPE2.0 extension : number of repetitions after the first play
+        ImageFormat::Gif => bytes
+            .windows(11)
+            .position(|window| window == b"NETSCAPE2.0")
+            .and_then(|at| bytes.get(at + 11..at + 15))
+            .filter(|block| block[0] == 3 && block[1] == 1)
+            .map_or(1, |block| match u16::from_le_bytes([block[2], block[3]]) {
+                0 => 0,
+                repeat => repeat as u32 + 1,
+            }),
+        ImageFormat::Png => png_chunks(bytes)
+            .find(|(kind, _)| kind == b"acTL")
+            .and_then(|(_, data)| data.get(4..8))
+            .map_or(0, |n| u32::from_be_bytes([n[0], n[1], n[2], n[3]])),
+        ImageFormat::WebP => riff_chunks(bytes)
+            .find(|(kind, _)| kind == b"ANIM")
+            .and_then(|(_, data)| data.get(4..6))
+            .map_or(0, |n| u16::from_le_bytes([n[0], n[1]]) as u32),
+        _ => 1,
+    }
+}
+
+/// First frame of an animated WebP, without decoding the next ones
+pub fn webp_first_frame(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> Result<DynamicImage, ProcessingError> {
+    match webp_frames(bytes, limits, 1)?.pop() {
+        Some(frame) => Ok(frame.image),
+        None => Err(ErrorCode::ImageEmpty.into()),
+    }
+}
+
+/// Every ANMF frame is decoded on its own, after checking its header against the canvas,
+/// then composited on the canvas with its offset, blending and disposal
+fn webp_frames(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+    max_frames: usize,
+) -> Result<Vec<AnimationFrame>, ProcessingError> {
+    let (width, height) = riff_chunks(bytes)
+        .find(|(kind, _)| kind == b"VP8X")
+        .and_then(|(_, data)| Some((u24_le(data, 4)? + 1, u24_le(data, 7)? + 1)))
+        .ok_or_else(|| webp_error("missing VP8X chunk"))?;
+
+    let mut canvas = RgbaImage::new(width, height);
+    let mut frames = Vec::new();
+    //Area to clear before drawing the next frame
+    let mut dispose: Option<(u32, u32, u32, u32)> = None;
+    let anmf_chunks = riff_chunks(bytes).filter(|(kind, _)| kind == b"ANMF");
+    for (index, (_, data)) in anmf_chunks.take(max_frames).enumerate() {
+        let header = (|| {
+            Some((
+                u24_le(data, 0)? * 2,
+                u24_le(data, 3)? * 2,
+                u24_le(data, 6)? + 1,
+                u24_le(data, 9)? + 1,
+                u24_le(data, 12)?,
+                *data.get(15)?,
+            ))
+        })();
+        let (x, y, frame_w, frame_h, duration, flags) =
+            header.ok_or_else(|| webp_error("truncated ANMF chunk"))?;
+        if x + frame_w > width || y + frame_h > height {
+            error!(
+                "WebP frame {} of {}x{} at ({}, {}) is outside of the {}x{} canvas",
+                index, frame_w, frame_h, x, y, width, height
+            );
+            return Err(webp_error("frame outside of the canvas")
+                .with_detail("frame", index)
+                .with_detail("width", frame_w)
+                .with_detail("height", frame_h));
+        }
+
+        if let Some((dx, dy, dw, dh)) = dispose.take() {
+            for py in dy..(dy + dh).min(height) {
+                for px in dx..(dx + dw).min(width) {
+                    canvas.put_pixel(px, py, image::Rgba([0, 0, 0, 0]));
+                }
+            }
+        }
+
+        let frame = webp_frame(data, (frame_w, frame_h), limits)
+            .map_err(|error| error.with_detail("frame", index))?;
+        if flags & 0x02 == 0 {
+            imageops::overlay(&mut canvas, &frame, x as i64, y as i64);
+        } else {
+            imageops::replace(&mut canvas, &frame, x as i64, y as i64);
+        }
+        if flags & 0x01 != 0 {
+            dispose = Some((x, y, frame.width(), frame.height()));
+        }
+
+        frames.push(AnimationFrame {
+            image: DynamicImage::ImageRgba8(canvas.clone()),
+            delay_ms: if duration == 0 { DEFAULT_DELAY_MS } else { duration },
+        });
+    }
+    Ok(frames)
+}
+
+//Decode the VP8 / VP8L bitstream of a frame, with the alpha of its ALPH chunk
+//The bitstream must have the size declared by the ANMF header
+fn webp_frame(
+    anmf: &[u8],
+    size: (u32, u32),
+    limits: &DecodeLimits,
+) -> Result<RgbaImage, ProcessingError> {
+    let (mut bitstream, mut alpha) = (None, None);
+    for (kind, data) in riff_chunks_from(anmf, 16) {
+        match kind {
+            b"VP8 " | b"VP8L" => bitstream = Some((kind, data)),
+            b"ALPH" => alpha = Some(data),
+            _ => {}
+        }
+    }
+    let (kind, data) = bitstream.ok_or_else(|| webp_error("ANMF chunk without image data"))?;
+    match webp_chunk_header(kind, data) {
+        Some((width, height, _)) if (width, height) == size => {}
+        _ => return Err(webp_error("bitstream size differs from the ANMF header")),
+    }
+
+    let mut frame = decode_webp(kind, data, limits)?.to_rgba8();
+    //VP8L has its own alpha, ALPH only comes with a lossy bitstream
+    if let (b"VP8 ", Some(alpha)) = (kind, alpha) {
+        let alpha = webp_alpha(alpha, frame.width(), frame.height(), limits)?;
+        for (pixel, alpha) in frame.pixels_mut().zip(alpha) {
+            pixel[3] = alpha;
+        }
+    }
+    Ok(frame)
+}
+
+//Wrap a VP8 / VP8L bitstream in its own WebP file
+fn decode_webp(
+    kind: &[u8],
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<DynamicImage, ProcessingError> {
+    let padding = data.len() & 1;
+    let mut file = Vec::with_capacity(20 + data.len() + padding);
+    file.extend_from_slice(b"RIFF");
+    file.extend_from_slice(&((12 + data.len() + padding) as u32).to_le_bytes());
+    file.extend_from_slice(b"WEBP");
+    file.extend_from_slice(kind);
+    file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    file.extend_from_slice(data);
+    file.resize(file.len() + padding, 0);
+
+    let mut reader = image::io::Reader::with_format(Cursor::new(file), ImageFormat::WebP);
+    reader.limits(limits.image_limits());
+    reader.decode().map_err(decode_error)
+}
+
+/// ALPH chunk : a header byte, then the alpha plane either raw or as the green
+/// channel of a headerless VP8L image, each value filtered from its neighbours
+fn webp_alpha(
+    alph: &[u8],
+    width: u32,
+    height: u32,
+    limits: &DecodeLimits,
+) -> Result<Vec<u8>, ProcessingError> {
+    let (&header, data) = alph
+        .split_first()
+        .ok_or_else(|| webp_error("empty ALPH chunk"))?;
+    let (w, h) = (width as usize, height as usize);
+    let mut alpha = match header & 0x03 {
+        0 => data
+            .get(..w * h)
+            .ok_or_else(|| webp_error("truncated ALPH chunk"))?
+            .to_vec(),
+        1 => {
+            //Give back its header to the image stream : signature, size, no alpha, version 0
+            let mut bitstream = vec![0x2f];
+            bitstream.extend_from_slice(&((width - 1) | (height - 1) << 14).to_le_bytes());
+            bitstream.extend_from_slice(data);
+            let plane = decode_webp(b"VP8L", &bitstream, limits)?.to_rgba8();
+            plane.pixels().map(|pixel| pixel[1]).collect()
+        }
+        _ => return Err(webp_error("unknown ALPH compression")),
+    };
+
+    //Horizontal, vertical or gradient : the first row is predicted from the left,
+    //the first column from above, the top left value is kept
+    let filter = (header >> 2) & 0x03;
+    if filter != 0 {
+        for y in 0..h {
+            for x in 0..w {
+                let at = y * w + x;
+                let predictor = match (x, y) {
+                    (0, 0) => continue,
+                    (0, _) => alpha[at - w],
+                    (_, 0) => alpha[at - 1],
+                    _ => match filter {
+                        1 => alpha[at - 1],
+                        2 => alpha[at - w],
+                        _ => (alpha[at - 1] as i16 + alpha[at - w] as i16
+                            - alpha[at - w - 1] as i16)
+                            .clamp(0, 255) as u8,
+                    },
+                };
+                alpha[at] = alpha[at].wrapping_add(predictor);
+            }
+        }
+    }
+    Ok(alpha)
+}
+
+fn webp_error(cause: &str) -> ProcessingError {
+    ProcessingError::from(ErrorCode::UnableToDecode)
+        .with_cause(cause)
+        .with_detail("format", "WebP")
+}
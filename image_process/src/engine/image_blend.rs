@@ -0,0 +1,193 @@
+use image::{imageops, DynamicImage, GenericImageView, Rgba, RgbaImage};
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+/// How a filter layer is composited on the image (same formulas as CSS / Photoshop)
+#[wasm_bindgen]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum BlendMode {
+    #[default]
+    NORMAL,
+    MULTIPLY,
+    SCREEN,
+    OVERLAY,
+    SOFT_LIGHT,
+    HARD_LIGHT,
+    COLOR_DODGE,
+    COLOR_BURN,
+    DARKEN,
+    LIGHTEN,
+    DIFFERENCE,
+    EXCLUSION,
+    HUE,
+    SATURATION,
+    COLOR,
+    LUMINOSITY,
+}
+
+type Rgb = [f32; 3];
+
+impl BlendMode {
+    /// Blend one channel, values between 0 and 1
+    /// `backdrop` is the image, `source` is the layer drawn on the top
+    fn blend_channel(&self, backdrop: f32, source: f32) -> f32 {
+        match self {
+            Self::MULTIPLY => backdrop * source,
+            Self::SCREEN => BlendMode::screen(backdrop, source),
+            Self::OVERLAY => BlendMode::hard_light(source, backdrop),
+            Self::HARD_LIGHT => BlendMode::hard_light(backdrop, source),
+            Self::SOFT_LIGHT => {
+                if source <= 0.5 {
+                    backdrop - (1.0 - 2.0 * source) * backdrop * (1.0 - backdrop)
+                } else {
+                    let d = if backdrop <= 0.25 {
+                        ((16.0 * backdrop - 12.0) * backdrop + 4.0) * backdrop
+                    } else {
+                        backdrop.sqrt()
+                    };
+                    backdrop + (2.0 * source - 1.0) * (d - backdrop)
+                }
+            }
+            Self::COLOR_DODGE => {
+                if backdrop <= 0.0 {
+                    0.0
+                } else if source >= 1.0 {
+                    1.0
+                } else {
+                    (backdrop / (1.0 - source)).min(1.0)
+                }
+            }
+            Self::COLOR_BURN => {
+                if backdrop >= 1.0 {
+                    1.0
+                } else if source <= 0.0 {
+                    0.0
+                } else {
+                    1.0 - ((1.0 - backdrop) / source).min(1.0)
+                }
+            }
+            Self::DARKEN => backdrop.min(source),
+            Self::LIGHTEN => backdrop.max(source),
+            Self::DIFFERENCE => (backdrop - source).abs(),
+            Self::EXCLUSION => backdrop + source - 2.0 * backdrop * source,
+            _ => source,
+        }
+    }
+
+    fn screen(backdrop: f32, source: f32) -> f32 {
+        backdrop + source - backdrop * source
+    }
+
+    fn hard_light(backdrop: f32, source: f32) -> f32 {
+        if source <= 0.5 {
+            backdrop * 2.0 * source
+        } else {
+            BlendMode::screen(backdrop, 2.0 * source - 1.0)
+        }
+    }
+
+    fn luminosity(c: Rgb) -> f32 {
+        0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+    }
+
+    fn clip_color(c: Rgb) -> Rgb {
+        let l = BlendMode::luminosity(c);
+        let n = c[0].min(c[1]).min(c[2]);
+        let x = c[0].max(c[1]).max(c[2]);
+        c.map(|v| {
+            let mut v = v;
+            if n < 0.0 {
+                v = l + (v - l) * l / (l - n);
+            }
+            if x > 1.0 {
+                v = l + (v - l) * (1.0 - l) / (x - l);
+            }
+            v
+        })
+    }
+
+    fn set_luminosity(c: Rgb, l: f32) -> Rgb {
+        let d = l - BlendMode::luminosity(c);
+        BlendMode::clip_color(c.map(|v| v + d))
+    }
+
+    fn saturation(c: Rgb) -> f32 {
+        c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+    }
+
+    fn set_saturation(c: Rgb, s: f32) -> Rgb {
+        let max = c[0].max(c[1]).max(c[2]);
+        let min = c[0].min(c[1]).min(c[2]);
+        if max <= min {
+            return [0.0; 3];
+        }
+        c.map(|v| (v - min) * s / (max - min))
+    }
+
+    /// Blend the colors, non separable modes work on the whole RGB value
+    fn blend_rgb(&self, backdrop: Rgb, source: Rgb) -> Rgb {
+        match self {
+            Self::HUE => BlendMode::set_luminosity(
+                BlendMode::set_saturation(source, BlendMode::saturation(backdrop)),
+                BlendMode::luminosity(backdrop),
+            ),
+            Self::SATURATION => BlendMode::set_luminosity(
+                BlendMode::set_saturation(backdrop, BlendMode::saturation(source)),
+                BlendMode::luminosity(backdrop),
+            ),
+            Self::COLOR => BlendMode::set_luminosity(source, BlendMode::luminosity(backdrop)),
+            Self::LUMINOSITY => BlendMode::set_luminosity(backdrop, BlendMode::luminosity(source)),
+            _ => [0, 1, 2].map(|c| self.blend_channel(backdrop[c], source[c])),
+        }
+    }
+
+    /// Composite the source pixel on the backdrop pixel (W3C compositing formula)
+    pub fn blend_pixel(&self, backdrop: Rgba<u8>, source: Rgba<u8>) -> Rgba<u8> {
+        let alpha_b = backdrop[3] as f32 / 255.0;
+        let alpha_s = source[3] as f32 / 255.0;
+        let alpha_o = alpha_s + alpha_b * (1.0 - alpha_s);
+        if alpha_o <= 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+
+        let cb: Rgb = [0, 1, 2].map(|c| backdrop[c] as f32 / 255.0);
+        let cs: Rgb = [0, 1, 2].map(|c| source[c] as f32 / 255.0);
+        let blended = self.blend_rgb(cb, cs);
+
+        let mut result = [0u8; 4];
+        for c in 0..3 {
+            let mixed = (1.0 - alpha_b) * cs[c] + alpha_b * blended[c];
+            let co = (alpha_s * mixed + alpha_b * cb[c] * (1.0 - alpha_s)) / alpha_o;
+            result[c] = (co * 255.0).round().clamp(0.0, 255.0) as u8;
+        }
+        result[3] = (alpha_o * 255.0).round() as u8;
+        Rgba(result)
+    }
+}
+
+/// Composite the layer at (0, 0) on the top of the image
+pub fn blend_overlay(img: &mut DynamicImage, layer: &RgbaImage, mode: BlendMode) {
+    blend_layer(img, layer, 0, 0, mode);
+}
+
+/// Composite the layer at (x, y) on the top of the image, the layer can go outside the image
+pub fn blend_layer(img: &mut DynamicImage, layer: &RgbaImage, x: i64, y: i64, mode: BlendMode) {
+    if mode == BlendMode::NORMAL {
+        //Plain source-over, keep the color type of the image
+        imageops::overlay(img, layer, x, y);
+        return;
+    }
+
+    let (w, h) = img.dimensions();
+    let mut base = img.to_rgba8();
+    for (lx, ly, source) in layer.enumerate_pixels() {
+        let (px, py) = (x + lx as i64, y + ly as i64);
+        if px < 0 || py < 0 || px >= w as i64 || py >= h as i64 {
+            continue;
+        }
+        let pixel = base.get_pixel_mut(px as u32, py as u32);
+        *pixel = mode.blend_pixel(*pixel, *source);
+    }
+    *img = DynamicImage::ImageRgba8(base);
+}
@@ -0,0 +1,208 @@
+use image::{imageops, DynamicImage, GenericImageView};
+use log::*;
+use wasm_bindgen::prelude::*;
+
+use super::{EmbeddedMetadata, ErrorCode, ProcessingError, ImageProcessingResult, OutputOptions};
+
+/// Limits of the search done by `compress_to_size`
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone)]
+pub struct CompressionOptions {
+    //The shorter side of the image is never reduced below this value
+    pub min_dimension: u32,
+    //Quality range explored for lossy formats, from 1 to 100
+    pub min_quality: u8,
+    pub max_quality: u8,
+}
+
+#[wasm_bindgen]
+impl CompressionOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        min_dimension: Option<u32>,
+        min_quality: Option<u8>,
+        max_quality: Option<u8>,
+    ) -> CompressionOptions {
+        let default = CompressionOptions::default();
+        CompressionOptions {
+            min_dimension: min_dimension.unwrap_or(default.min_dimension),
+            min_quality: min_quality.unwrap_or(default.min_quality),
+            max_quality: max_quality.unwrap_or(default.max_quality),
+        }
+    }
+}
+
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        Self {
+            min_dimension: 16,
+            min_quality: 30,
+            max_quality: 95,
+        }
+    }
+}
+
+/// Encoded image with the settings found to reach the target size
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct CompressionResult {
+    result: ImageProcessingResult,
+    //None for lossless formats
+    quality: Option<u8>,
+    width: u32,
+    height: u32,
+}
+
+#[wasm_bindgen]
+impl CompressionResult {
+    pub fn get_result(&self) -> ImageProcessingResult {
+        self.result.clone()
+    }
+
+    pub fn get_quality(&self) -> Option<u8> {
+        self.quality
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn byte_length(&self) -> usize {
+        self.result.byte_length()
+    }
+}
+
+impl CompressionResult {
+    pub fn into_result(self) -> ImageProcessingResult {
+        self.result
+    }
+}
+
+/// Encode the image with its longer side reduced to `long_side` pixels
+fn encode_scaled(
+    img: &DynamicImage,
+    metadata: &EmbeddedMetadata,
+    long_side: u32,
+    output: &OutputOptions,
+) -> Result<(Vec<u8>, (u32, u32)), ProcessingError> {
+    let (w, h) = img.dimensions();
+    let long = w.max(h);
+    if long_side >= long {
+        return Ok((output.encode_with_metadata(img, metadata)?, (w, h)));
+    }
+
+    let new_w = ((w as u64 * long_side as u64) / long as u64).max(1) as u32;
+    let new_h = ((h as u64 * long_side as u64) / long as u64).max(1) as u32;
+    let scaled = img.resize_exact(new_w, new_h, imageops::FilterType::Lanczos3);
+    Ok((output.encode_with_metadata(&scaled, metadata)?, (new_w, new_h)))
+}
+
+/// Find the largest dimensions, then the highest quality (lossy formats only),
+/// giving an encoded image of at most `target_size` bytes
+/// Both searches are binary searches, the image is decoded only once by the caller
+/// The kept metadata is counted in the size
+pub fn compress_to_size(
+    img: &DynamicImage,
+    metadata: &EmbeddedMetadata,
+    target_size: usize,
+    options: CompressionOptions,
+    output: OutputOptions,
+) -> Result<CompressionResult, ProcessingError> {
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return Err(ErrorCode::ImageEmpty.into());
+    }
+
+    let lossy = output.format.is_lossy();
+    let max_quality = options.max_quality.clamp(1, 100);
+    let min_quality = options.min_quality.clamp(1, max_quality);
+    let with_quality = |quality: u8| OutputOptions { quality, ..output };
+    let finish = |(bytes, (width, height)): (Vec<u8>, (u32, u32)), quality: u8| {
+        info!(
+            "Target size reached : {} bytes (target = {}) - {}x{} - quality {}",
+            bytes.len(),
+            target_size,
+            width,
+            height,
+            quality
+        );
+        CompressionResult {
+            result: ImageProcessingResult::new(bytes, output.format),
+            quality: if lossy { Some(quality) } else { None },
+            width,
+            height,
+        }
+    };
+
+    //Full size with the best quality, nothing to search
+    let long = w.max(h);
+    let best = encode_scaled(img, metadata, long, &with_quality(max_quality))?;
+    if best.0.len() <= target_size {
+        return Ok(finish(best, max_quality));
+    }
+
+    //Largest size fitting with the lowest quality
+    let short = w.min(h);
+    let min_long = if short <= options.min_dimension {
+        long
+    } else {
+        ((options.min_dimension as u64 * long as u64).div_ceil(short as u64) as u32).max(1)
+    };
+    let floor = with_quality(min_quality);
+    let mut fitting = encode_scaled(img, metadata, long, &floor)?;
+    let mut long_side = long;
+    if fitting.0.len() > target_size {
+        fitting = encode_scaled(img, metadata, min_long, &floor)?;
+        if fitting.0.len() > target_size {
+            error!(
+                "Target size {} bytes unreachable, {} bytes at {}x{}",
+                target_size,
+                fitting.0.len(),
+                fitting.1 .0,
+                fitting.1 .1
+            );
+            return Err(ProcessingError::from(ErrorCode::TargetSizeUnreachable)
+                .with_detail("target_size", target_size)
+                .with_detail("smallest_size", fitting.0.len()));
+        }
+
+        //`lo` always fits, `hi` never fits
+        let (mut lo, mut hi) = (min_long, long);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            let candidate = encode_scaled(img, metadata, mid, &floor)?;
+            trace!("Long side {} -> {} bytes", mid, candidate.0.len());
+            if candidate.0.len() <= target_size {
+                lo = mid;
+                fitting = candidate;
+            } else {
+                hi = mid;
+            }
+        }
+        long_side = lo;
+    }
+
+    if !lossy {
+        return Ok(finish(fitting, max_quality));
+    }
+
+    //Highest quality fitting at this size, `lo` always fits
+    let (mut lo, mut hi) = (min_quality, max_quality);
+    while hi > lo {
+        let mid = lo + (hi - lo).div_ceil(2);
+        let candidate = encode_scaled(img, metadata, long_side, &with_quality(mid))?;
+        trace!("Quality {} -> {} bytes", mid, candidate.0.len());
+        if candidate.0.len() <= target_size {
+            lo = mid;
+            fitting = candidate;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    Ok(finish(fitting, lo))
+}
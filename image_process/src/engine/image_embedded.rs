@@ -0,0 +1,365 @@
+use exif::{experimental::Writer, Field, In, Tag, Value};
+use image::ImageFormat;
+use log::*;
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+
+use super::image_metadata::{self, jpeg_segments, png_chunks, riff_chunks};
+use super::{ProcessingError, OutputFormat};
+
+const JPEG_ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+const JPEG_EXIF_HEADER: &[u8] = b"Exif\0\0";
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+//Biggest payload of a JPEG segment, the length takes 2 bytes
+const JPEG_SEGMENT_MAX: usize = 65533;
+
+/// Metadata copied from the input to the output, nothing is kept by default
+/// Only written in JPEG and PNG outputs
+///     - exif : camera, date, exposure... (every EXIF field except GPS and copyright)
+///     - gps : GPS position
+///     - copyright : Copyright and Artist fields
+///     - icc : color profile
+///     - xmp : XMP packet
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct MetadataOptions {
+    pub exif: bool,
+    pub gps: bool,
+    pub copyright: bool,
+    pub icc: bool,
+    pub xmp: bool,
+}
+
+#[wasm_bindgen]
+impl MetadataOptions {
+    /// Strip everything, set the fields to keep a whitelist
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> MetadataOptions {
+        MetadataOptions::default()
+    }
+
+    pub fn strip_all() -> MetadataOptions {
+        MetadataOptions::default()
+    }
+
+    pub fn keep_all() -> MetadataOptions {
+        MetadataOptions {
+            exif: true,
+            gps: true,
+            copyright: true,
+            icc: true,
+            xmp: true,
+        }
+    }
+}
+
+impl MetadataOptions {
+    pub fn is_strip_all(&self) -> bool {
+        *self == MetadataOptions::strip_all()
+    }
+
+    fn keep_exif_field(&self, field: &Field) -> bool {
+        match field.tag {
+            //Describe the pixels of the input, wrong after re-encoding
+            Tag::ImageWidth
+            | Tag::ImageLength
+            | Tag::BitsPerSample
+            | Tag::Compression
+            | Tag::PhotometricInterpretation
+            | Tag::SamplesPerPixel
+            | Tag::PlanarConfiguration
+            | Tag::RowsPerStrip
+            | Tag::PixelXDimension
+            | Tag::PixelYDimension => false,
+            Tag(exif::Context::Tiff, image_metadata::TIFF_TAG_ICC_PROFILE) => false,
+            Tag::Copyright | Tag::Artist => self.copyright,
+            Tag(exif::Context::Gps, _) => self.gps,
+            _ => self.exif,
+        }
+    }
+}
+
+/// EXIF, ICC and XMP blocks of an encoded image
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedMetadata {
+    //Raw TIFF structure, without the `Exif\0\0` header
+    exif: Option<Vec<u8>>,
+    icc: Option<Vec<u8>>,
+    xmp: Option<Vec<u8>>,
+    //The pixels have been rotated according to the EXIF orientation
+    oriented: bool,
+}
+
+impl EmbeddedMetadata {
+    /// Extract the metadata of a JPEG, PNG, WebP or TIFF image
+    pub fn read(bytes: &[u8], oriented: bool) -> EmbeddedMetadata {
+        let exif = exif::Reader::new()
+            .read_from_container(&mut Cursor::new(bytes))
+            .ok();
+        let mut metadata = EmbeddedMetadata {
+            exif: exif.as_ref().map(|exif| exif.buf().to_vec()),
+            icc: None,
+            xmp: None,
+            oriented,
+        };
+
+        match image::guess_format(bytes) {
+            Ok(ImageFormat::Jpeg) => {
+                let mut icc_chunks: Vec<(u8, &[u8])> = jpeg_segments(bytes)
+                    .filter(|(marker, data)| *marker == 0xE2 && data.starts_with(JPEG_ICC_HEADER))
+                    .filter_map(|(_, data)| {
+                        Some((
+                            *data.get(JPEG_ICC_HEADER.len())?,
+                            data.get(JPEG_ICC_HEADER.len() + 2..)?,
+                        ))
+                    })
+                    .collect();
+                icc_chunks.sort_by_key(|(sequence, _)| *sequence);
+                if !icc_chunks.is_empty() {
+                    metadata.icc = Some(
+                        icc_chunks
+                            .iter()
+                            .flat_map(|(_, data)| data.to_vec())
+                            .collect(),
+                    );
+                }
+                metadata.xmp = jpeg_segments(bytes)
+                    .find(|(marker, data)| *marker == 0xE1 && data.starts_with(JPEG_XMP_HEADER))
+                    .map(|(_, data)| data[JPEG_XMP_HEADER.len()..].to_vec());
+            }
+            Ok(ImageFormat::Png) => {
+                for (kind, data) in png_chunks(bytes) {
+                    match kind {
+                        b"iCCP" => metadata.icc = EmbeddedMetadata::read_png_icc(data),
+                        b"iTXt" => {
+                            if let Some(xmp) = EmbeddedMetadata::read_png_xmp(data) {
+                                metadata.xmp = Some(xmp);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Ok(ImageFormat::WebP) => {
+                for (kind, data) in riff_chunks(bytes) {
+                    match kind {
+                        b"ICCP" => metadata.icc = Some(data.to_vec()),
+                        b"XMP " => metadata.xmp = Some(data.to_vec()),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(ImageFormat::Tiff) => {
+                metadata.icc = exif.as_ref().and_then(|exif| {
+                    match &exif
+                        .get_field(
+                            Tag(exif::Context::Tiff, image_metadata::TIFF_TAG_ICC_PROFILE),
+                            In::PRIMARY,
+                        )?
+                        .value
+                    {
+                        Value::Undefined(data, _) | Value::Byte(data) => Some(data.clone()),
+                        _ => None,
+                    }
+                })
+            }
+            _ => {}
+        }
+        metadata
+    }
+
+    /// Profile name, compression method then the zlib stream
+    fn read_png_icc(data: &[u8]) -> Option<Vec<u8>> {
+        let name_end = data.iter().position(|b| *b == 0)?;
+        let compressed = data.get(name_end + 2..)?;
+        miniz_oxide::inflate::decompress_to_vec_zlib(compressed)
+            .map_err(|e| warn!("Invalid PNG ICC profile : {:?}", e))
+            .ok()
+    }
+
+    /// Uncompressed iTXt chunk with the XMP keyword
+    fn read_png_xmp(data: &[u8]) -> Option<Vec<u8>> {
+        let rest = data
+            .strip_prefix(PNG_XMP_KEYWORD)?
+            .strip_prefix(&[0u8, 0])?;
+        //Skip the compression method, the language tag and the translated keyword
+        let rest = rest.get(1..)?;
+        let lang_end = rest.iter().position(|b| *b == 0)?;
+        let rest = &rest[lang_end + 1..];
+        let translated_end = rest.iter().position(|b| *b == 0)?;
+        Some(rest[translated_end + 1..].to_vec())
+    }
+
+    /// EXIF fields allowed by the options, rewritten in a new TIFF structure
+    fn filtered_exif(&self, options: &MetadataOptions) -> Option<Vec<u8>> {
+        let exif = exif::Reader::new().read_raw(self.exif.clone()?).ok()?;
+        let orientation = Field {
+            tag: Tag::Orientation,
+            ifd_num: In::PRIMARY,
+            value: Value::Short(vec![1]),
+        };
+
+        let mut writer = Writer::new();
+        let mut count = 0;
+        for field in exif.fields() {
+            if field.ifd_num != In::PRIMARY || !options.keep_exif_field(field) {
+                continue;
+            }
+            //The pixels are already upright
+            if field.tag == Tag::Orientation && self.oriented {
+                writer.push_field(&orientation);
+            } else {
+                writer.push_field(field);
+            }
+            count += 1;
+        }
+        if count == 0 {
+            return None;
+        }
+
+        let mut tiff = Cursor::new(Vec::new());
+        match writer.write(&mut tiff, exif.little_endian()) {
+            Ok(_) => Some(tiff.into_inner()),
+            Err(e) => {
+                warn!("Unable to write the EXIF data : {}", e);
+                None
+            }
+        }
+    }
+
+    /// Add the metadata allowed by the options to the encoded image
+    pub fn embed(
+        &self,
+        encoded: Vec<u8>,
+        format: OutputFormat,
+        options: &MetadataOptions,
+    ) -> Result<Vec<u8>, ProcessingError> {
+        if options.is_strip_all() {
+            return Ok(encoded);
+        }
+        let exif = self.filtered_exif(options);
+        let icc = self.icc.as_ref().filter(|_| options.icc);
+        let xmp = self.xmp.as_ref().filter(|_| options.xmp);
+        if exif.is_none() && icc.is_none() && xmp.is_none() {
+            return Ok(encoded);
+        }
+        trace!(
+            "Embed metadata : exif = {}, icc = {}, xmp = {}",
+            exif.is_some(),
+            icc.is_some(),
+            xmp.is_some()
+        );
+
+        match format {
+            OutputFormat::JPEG => Ok(embed_jpeg(encoded, exif.as_deref(), icc, xmp)),
+            OutputFormat::PNG => Ok(embed_png(encoded, exif.as_deref(), icc, xmp)),
+            _ => {
+                warn!("Metadata can't be written in {:?}, dropped", format);
+                Ok(encoded)
+            }
+        }
+    }
+}
+
+fn jpeg_segment(marker: u8, parts: &[&[u8]]) -> Option<Vec<u8>> {
+    let length: usize = parts.iter().map(|p| p.len()).sum();
+    if length > JPEG_SEGMENT_MAX - 2 {
+        warn!(
+            "Metadata too big for a JPEG segment ({} bytes), dropped",
+            length
+        );
+        return None;
+    }
+    let mut segment = vec![0xFF, marker];
+    segment.extend_from_slice(&((length + 2) as u16).to_be_bytes());
+    for part in parts {
+        segment.extend_from_slice(part);
+    }
+    Some(segment)
+}
+
+/// Insert the segments after SOI and the JFIF header
+fn embed_jpeg(
+    encoded: Vec<u8>,
+    exif: Option<&[u8]>,
+    icc: Option<&Vec<u8>>,
+    xmp: Option<&Vec<u8>>,
+) -> Vec<u8> {
+    let mut segments = Vec::new();
+    if let Some(exif) = exif {
+        segments.extend(jpeg_segment(0xE1, &[JPEG_EXIF_HEADER, exif]));
+    }
+    if let Some(xmp) = xmp {
+        segments.extend(jpeg_segment(0xE1, &[JPEG_XMP_HEADER, xmp]));
+    }
+    if let Some(icc) = icc {
+        //The profile is split in numbered chunks
+        let chunk_size = JPEG_SEGMENT_MAX - 2 - JPEG_ICC_HEADER.len() - 2;
+        let chunks: Vec<&[u8]> = icc.chunks(chunk_size).collect();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let numbers = [index as u8 + 1, chunks.len() as u8];
+            segments.extend(jpeg_segment(0xE2, &[JPEG_ICC_HEADER, &numbers, chunk]));
+        }
+    }
+
+    //SOI (2) + APP0 marker and length (4) + JFIF data
+    let insert_at = match jpeg_segments(&encoded).next() {
+        Some((0xE0, data)) => 6 + data.len(),
+        _ => 2,
+    };
+    let segments = segments.concat();
+    let mut output = Vec::with_capacity(encoded.len() + segments.len());
+    output.extend_from_slice(&encoded[..insert_at]);
+    output.extend(segments);
+    output.extend_from_slice(&encoded[insert_at..]);
+    output
+}
+
+fn png_chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    chunk.extend_from_slice(&crc.finalize().to_be_bytes());
+    chunk
+}
+
+/// Insert the chunks after IHDR, before the image data
+fn embed_png(
+    encoded: Vec<u8>,
+    exif: Option<&[u8]>,
+    icc: Option<&Vec<u8>>,
+    xmp: Option<&Vec<u8>>,
+) -> Vec<u8> {
+    let mut chunks = Vec::new();
+    if let Some(icc) = icc {
+        let mut data = b"ICC profile\0\0".to_vec();
+        data.extend(miniz_oxide::deflate::compress_to_vec_zlib(icc, 6));
+        chunks.extend(png_chunk(b"iCCP", &data));
+    }
+    if let Some(exif) = exif {
+        chunks.extend(png_chunk(b"eXIf", exif));
+    }
+    if let Some(xmp) = xmp {
+        let mut data = PNG_XMP_KEYWORD.to_vec();
+        data.extend_from_slice(&[0, 0, 0, 0, 0]);
+        data.extend_from_slice(xmp);
+        chunks.extend(png_chunk(b"iTXt", &data));
+    }
+
+    //Signature (8) + IHDR (12 + 13)
+    let insert_at = match png_chunks(&encoded).next() {
+        Some((b"IHDR", data)) => 8 + 12 + data.len(),
+        _ => 8,
+    };
+    let mut output = Vec::with_capacity(encoded.len() + chunks.len());
+    output.extend_from_slice(&encoded[..insert_at]);
+    output.extend(chunks);
+    output.extend_from_slice(&encoded[insert_at..]);
+    output
+}
@@ -1,16 +1,23 @@
+use js_sys::{Object, Reflect};
 use std::fmt::Debug;
 use std::fmt::{Formatter, Display, Result as FmtResult};
 use std::error::Error;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
+#[derive(Copy, Clone, PartialEq, Eq)]
 pub enum ErrorCode {
     UnableToDecode,
     InvalidParsing,
     UnableToSave,
     NoColorInput,
     NotImplemented,
-    ImageEmpty
+    ImageEmpty,
+    UnknownPreset,
+    TargetSizeUnreachable,
+    LimitExceeded,
+    FrameNotFound,
+    InvalidCrop
 }
 
 impl ErrorCode {
@@ -23,6 +30,38 @@ impl ErrorCode {
             Self::NoColorInput => "No color to apply for filter",
             Self::NotImplemented => "Not implemented yet",
             Self::ImageEmpty => "The image is empty",
+            Self::UnknownPreset => "Unknown preset",
+            Self::TargetSizeUnreachable => "Unable to reach the target size",
+            Self::LimitExceeded => "The image exceeds the decoding limits",
+            Self::FrameNotFound => "No frame at this index",
+            Self::InvalidCrop => "The crop area is outside the image",
+        }
+    }
+
+    //Stable identifier, to be tested in front end
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidParsing => "INVALID_PARSING",
+            Self::UnableToDecode => "UNABLE_TO_DECODE",
+            Self::UnableToSave => "UNABLE_TO_SAVE",
+            Self::NoColorInput => "NO_COLOR_INPUT",
+            Self::NotImplemented => "NOT_IMPLEMENTED",
+            Self::ImageEmpty => "IMAGE_EMPTY",
+            Self::UnknownPreset => "UNKNOWN_PRESET",
+            Self::TargetSizeUnreachable => "TARGET_SIZE_UNREACHABLE",
+            Self::LimitExceeded => "LIMIT_EXCEEDED",
+            Self::FrameNotFound => "FRAME_NOT_FOUND",
+            Self::InvalidCrop => "INVALID_CROP",
+        }
+    }
+
+    //Stage where this error usually happens
+    fn default_stage(&self) -> ErrorStage {
+        match self {
+            Self::InvalidParsing | Self::ImageEmpty => ErrorStage::INPUT,
+            Self::UnableToDecode | Self::LimitExceeded => ErrorStage::DECODE,
+            Self::UnableToSave => ErrorStage::ENCODE,
+            _ => ErrorStage::PROCESS,
         }
     }
 }
@@ -40,4 +79,140 @@ impl Debug for ErrorCode {
 }
 
 //Compatible with std::error::Error
-impl Error for ErrorCode { }
\ No newline at end of file
+impl Error for ErrorCode { }
+
+/// Step of the processing where the error happened
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorStage {
+    //Reading the base64 / data URL / bytes or the options
+    INPUT,
+    DECODE,
+    PROCESS,
+    ENCODE,
+}
+
+impl ErrorStage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::INPUT => "input",
+            Self::DECODE => "decode",
+            Self::PROCESS => "process",
+            Self::ENCODE => "encode",
+        }
+    }
+}
+
+/// Error with its context : code, stage, underlying cause and details
+/// Given to the front end as an `Error` with `code`, `stage`, `cause` and `details` properties
+#[derive(Clone, PartialEq)]
+pub struct ProcessingError {
+    code: ErrorCode,
+    stage: ErrorStage,
+    cause: Option<String>,
+    details: Vec<(&'static str, String)>,
+}
+
+impl ProcessingError {
+    pub fn new(code: ErrorCode, stage: ErrorStage) -> ProcessingError {
+        ProcessingError {
+            code,
+            stage,
+            cause: None,
+            details: Vec::new(),
+        }
+    }
+
+    /// Message of the underlying error (image crate, base64, serde...)
+    pub fn with_cause<C: Display>(mut self, cause: C) -> ProcessingError {
+        self.cause = Some(cause.to_string());
+        self
+    }
+
+    pub fn with_detail<V: Display>(mut self, key: &'static str, value: V) -> ProcessingError {
+        self.details.push((key, value.to_string()));
+        self
+    }
+
+    pub fn at_stage(mut self, stage: ErrorStage) -> ProcessingError {
+        self.stage = stage;
+        self
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn stage(&self) -> ErrorStage {
+        self.stage
+    }
+
+    pub fn cause(&self) -> Option<&str> {
+        self.cause.as_deref()
+    }
+
+    pub fn detail(&self, key: &str) -> Option<&str> {
+        self.details
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn message(&self) -> String {
+        match &self.cause {
+            Some(cause) => format!("{} : {}", self.code.message(), cause),
+            None => self.code.message().to_string(),
+        }
+    }
+}
+
+impl From<ErrorCode> for ProcessingError {
+    fn from(code: ErrorCode) -> Self {
+        ProcessingError::new(code, code.default_stage())
+    }
+}
+
+impl PartialEq<ErrorCode> for ProcessingError {
+    fn eq(&self, code: &ErrorCode) -> bool {
+        self.code == *code
+    }
+}
+
+impl Display for ProcessingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Debug for ProcessingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "[{}] {} ({})", self.code.code(), self.message(), self.stage.name())?;
+        for (key, value) in &self.details {
+            write!(f, " {}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ProcessingError { }
+
+//JS `Error` object with the context as properties
+impl From<ProcessingError> for JsValue {
+    fn from(error: ProcessingError) -> Self {
+        let js_error = js_sys::Error::new(&error.message());
+        let details = Object::new();
+        for (key, value) in &error.details {
+            let _ = Reflect::set(&details, &(*key).into(), &value.into());
+        }
+        let properties: [(&str, JsValue); 4] = [
+            ("code", error.code.code().into()),
+            ("stage", error.stage.name().into()),
+            ("cause", error.cause.as_deref().map_or(JsValue::NULL, JsValue::from)),
+            ("details", details.into()),
+        ];
+        for (key, value) in properties.iter() {
+            let _ = Reflect::set(&js_error, &(*key).into(), value);
+        }
+        js_error.into()
+    }
+}
@@ -1,11 +1,12 @@
 use image::*;
-use log::info;
+use log::{error, info};
+use serde::Deserialize;
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use super::ErrorCode;
+use super::{image_adjustments, image_blend, BlendMode, ErrorCode, ProcessingError, Gradient};
 
 #[wasm_bindgen]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Deserialize)]
 pub enum FilterPixelType {
     VERTICAL,
     HORIZONTAL,
@@ -14,14 +15,14 @@ pub enum FilterPixelType {
 }
 
 #[wasm_bindgen]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Deserialize)]
 pub enum GradientDirection {
     VERTICAL,
     HORIZONTAL,
 }
 //Basic Color enum to be instanciate from front
 #[wasm_bindgen]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Deserialize)]
 pub struct ColorRgba {
     pub red: u8,
     pub green: u8,
@@ -59,18 +60,76 @@ impl From<Rgba<u8>> for ColorRgba {
     }
 }
 
-pub fn filter_pixel(img: &mut DynamicImage, pixelType: FilterPixelType, color: ColorRgba) -> Result<DynamicImage, ErrorCode> {
-    filter_base(img, |x, y| {
-        if x % 4 == 0 {
+/// Colors used by the band filter when none are given
+pub fn default_band_colors() -> Vec<ColorRgba> {
+    vec![
+        ColorRgba::new(214, 110, 250, 150),
+        ColorRgba::new(155, 100, 220, 150),
+        ColorRgba::new(150, 120, 240, 150),
+        ColorRgba::new(95, 105, 220, 150),
+        ColorRgba::new(110, 150, 250, 160),
+    ]
+}
+
+/// Distance in pixel between two lines of the pixel filter
+pub const DEFAULT_PIXEL_SPACING: u32 = 4;
+/// Width in pixel of the lines of the pixel filter
+pub const DEFAULT_PIXEL_THICKNESS: u32 = 1;
+
+/// Return true if the pixel (x, y) is part of the pattern
+///     - VERTICAL : vertical lines
+///     - HORIZONTAL : horizontal lines
+///     - DIAGONAL : 45° hatch
+///     - CIRCLE : concentric circles around the centre of the image
+pub fn pixel_mask(
+    pixel_type: FilterPixelType,
+    (x, y): (u32, u32),
+    (w, h): (u32, u32),
+    spacing: u32,
+    thickness: u32,
+) -> bool {
+    let spacing = spacing.max(1);
+    let position = match pixel_type {
+        FilterPixelType::VERTICAL => x,
+        FilterPixelType::HORIZONTAL => y,
+        FilterPixelType::DIAGONAL => x + y,
+        FilterPixelType::CIRCLE => {
+            let dx = x as f32 - w as f32 / 2.0;
+            let dy = y as f32 - h as f32 / 2.0;
+            (dx * dx + dy * dy).sqrt() as u32
+        }
+    };
+    position % spacing < thickness
+}
+
+pub fn filter_pixel(
+    img: &mut DynamicImage,
+    pixel_type: FilterPixelType,
+    color: ColorRgba,
+    spacing: u32,
+    thickness: u32,
+    blend_mode: BlendMode,
+) -> Result<DynamicImage, ProcessingError> {
+    let dimensions = img.dimensions();
+    info!(
+        "Filter pixel {:?} - spacing = {}px - thickness = {}px",
+        pixel_type, spacing, thickness
+    );
+    filter_base(img, blend_mode, |x, y| {
+        if pixel_mask(pixel_type, (x, y), dimensions, spacing, thickness) {
             color.into()
         } else {
-            image::Rgba([255 as u8, 255, 255, 0])
+            image::Rgba([255u8, 255, 255, 0])
         }
     })
 }
 
 /// Apply the filter function in parameter
-fn filter_base<F>(img: &mut DynamicImage, func: F) -> Result<DynamicImage, ErrorCode>
+fn filter_base<F>(
+    img: &mut DynamicImage,
+    blend_mode: BlendMode,
+    func: F,
+) -> Result<DynamicImage, ProcessingError>
 where
     F: FnMut(u32, u32) -> Rgba<u8>,
 {
@@ -80,11 +139,8 @@ where
     //Apply function pass in parameters
     let filter = ImageBuffer::from_fn(w, h, func);
 
-    //Get back to DynamicImage
-    let filter_dynamic = DynamicImage::ImageRgba8(filter);
-
     //And then apply the overlay
-    imageops::overlay(img, &filter_dynamic, 0, 0);
+    image_blend::blend_overlay(img, &filter, blend_mode);
 
     Ok(img.to_owned())
 }
@@ -94,7 +150,8 @@ pub fn filter_gradient(
     color_from: Rgba<u8>,
     color_to: Rgba<u8>,
     gradient: GradientDirection,
-) -> Result<DynamicImage, ErrorCode> {
+    blend_mode: BlendMode,
+) -> Result<DynamicImage, ProcessingError> {
     let mut img_buf = RgbaImage::new(img.width(), img.height());
 
     match gradient {
@@ -106,24 +163,140 @@ pub fn filter_gradient(
         }
     }
 
-    imageops::overlay(img, &img_buf, 0, 0);
+    image_blend::blend_overlay(img, &img_buf, blend_mode);
 
     Ok(img.to_owned())
 }
 
-pub fn filter_sobel(img: DynamicImage) -> Result<DynamicImage, ErrorCode> {
+/// Draw a multi-stop linear / radial / conic gradient, composited with its blend mode
+pub fn filter_custom_gradient(
+    img: &mut DynamicImage,
+    gradient: &Gradient,
+) -> Result<DynamicImage, ProcessingError> {
+    let (w, h) = img.dimensions();
+    match gradient.render(w, h) {
+        Some(layer) => {
+            image_blend::blend_overlay(img, &layer, gradient.get_blend_mode());
+            Ok(img.to_owned())
+        }
+        None => {
+            error!("No color stop in the gradient");
+            Err(ErrorCode::NoColorInput.into())
+        }
+    }
+}
+
+pub fn filter_sobel(img: DynamicImage) -> Result<DynamicImage, ProcessingError> {
     let gray_image: GrayImage = img.to_luma8();
     let sobel = imageproc::gradients::sobel_gradients(&gray_image);
     Ok(DynamicImage::from(sobel))
 }
 
+/// Part of the half diagonal left untouched by the vignette
+pub const DEFAULT_VIGNETTE_RADIUS: f32 = 0.4;
+
+/// Darken the corners, `strength` from 0 to 1 (black corners)
+/// `radius` is the part of the half diagonal (0 to 1) left untouched around the centre
+pub fn filter_vignette(
+    img: DynamicImage,
+    strength: f32,
+    radius: f32,
+) -> Result<DynamicImage, ProcessingError> {
+    let strength = strength.clamp(0.0, 1.0);
+    let radius = radius.clamp(0.0, 0.99);
+    let (w, h) = img.dimensions();
+    let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+    let mut buffer = image_adjustments::to_working(&img, false);
+    for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+        //Elliptic distance to the centre, 1 in the corners
+        let dx = (x as f32 + 0.5 - cx) / cx;
+        let dy = (y as f32 + 0.5 - cy) / cy;
+        let distance = ((dx * dx + dy * dy) / 2.0).sqrt();
+        let t = ((distance - radius) / (1.0 - radius)).clamp(0.0, 1.0);
+        let factor = 1.0 - strength * t * t * (3.0 - 2.0 * t);
+        for c in 0..3 {
+            pixel[c] *= factor;
+        }
+    }
+    info!(
+        "Vignette applied : strength = {} - radius = {}",
+        strength, radius
+    );
+    Ok(image_adjustments::from_working(buffer, &img, false))
+}
+
+/// Add monochrome film grain, `amount` from 0 to 1
+/// The noise only depends on `seed` and the pixel position, the result is reproducible
+pub fn filter_grain(
+    img: DynamicImage,
+    amount: f32,
+    seed: u32,
+) -> Result<DynamicImage, ProcessingError> {
+    let amount = amount.clamp(0.0, 1.0) * GRAIN_RANGE;
+    let mut buffer = image_adjustments::to_working(&img, false);
+    for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+        let noise = hash_noise(x, y, seed) * amount;
+        for c in 0..3 {
+            pixel[c] = (pixel[c] + noise).clamp(0.0, 1.0);
+        }
+    }
+    info!("Grain applied : amount = {} - seed = {}", amount, seed);
+    Ok(image_adjustments::from_working(buffer, &img, false))
+}
+
+//Largest change of a channel by the grain, at amount 1
+const GRAIN_RANGE: f32 = 0.2;
+
+/// Pseudo random value between -1 and 1 for a pixel
+fn hash_noise(x: u32, y: u32, seed: u32) -> f32 {
+    let mut hash =
+        x.wrapping_mul(0x8da6_b343) ^ y.wrapping_mul(0xd816_3841) ^ seed.wrapping_mul(0xcb1a_b31f);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1_e995);
+    hash ^= hash >> 15;
+    hash as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// Linear interpolation between two colors, ratio from 0 (from) to 1 (to)
+pub fn mix_colors(from: Rgba<u8>, to: Rgba<u8>, ratio: f32) -> Rgba<u8> {
+    let ratio = ratio.clamp(0.0, 1.0);
+    let mut color = [0u8; 4];
+    for (c, value) in color.iter_mut().enumerate() {
+        *value = (from[c] as f32 + (to[c] as f32 - from[c] as f32) * ratio).round() as u8;
+    }
+    Rgba(color)
+}
+
+/// Color of the band at `pos` (between 0 and `length`)
+/// `softness` is the part of the band (0 to 1) blended with its neighbours
+fn band_color_at(colors: &[Rgba<u8>], pos: u32, length: u32, softness: f32) -> Rgba<u8> {
+    let count = colors.len();
+    //Position in band unit, the integer part is the band index
+    let t = (pos as f32 + 0.5) * count as f32 / length as f32;
+    let index = (t as usize).min(count - 1);
+    let frac = t - index as f32;
+    let half = softness / 2.0;
+
+    if half > 0.0 && frac < half && index > 0 {
+        mix_colors(colors[index - 1], colors[index], (frac + half) / softness)
+    } else if half > 0.0 && frac > 1.0 - half && index + 1 < count {
+        mix_colors(colors[index], colors[index + 1], (frac - 1.0 + half) / softness)
+    } else {
+        colors[index]
+    }
+}
+
+/// Split the image in vertical or horizontal bands of the same size, one by color
+/// The remainder pixels are spread over the bands so the whole image is covered
 pub fn filter_band_color(
     img: &mut DynamicImage,
     colors: Vec<Rgba<u8>>,
-    direction: GradientDirection
-) -> Result<DynamicImage, ErrorCode> {
+    direction: GradientDirection,
+    softness: f32,
+    blend_mode: BlendMode,
+) -> Result<DynamicImage, ProcessingError> {
     info!("Filter col color start");
-    if colors.len() == 0 {
+    if colors.is_empty() {
         info!("No color found, exit");
         //Nothing to do, exit early
         let edited_imgage = img;
@@ -131,53 +304,18 @@ pub fn filter_band_color(
     }
 
     let (w, h) = img.dimensions();
-
-
-    match direction {
-        GradientDirection::VERTICAL => {
-            let col_dimension: usize = w as usize / colors.len();
+    let softness = softness.clamp(0.0, 1.0);
     info!(
-        "Image dimension = {}*{} - {} colors to be apply - {}px by vertical band",
+        "Image dimension = {}*{} - {} colors to be apply by {:?} band - softness = {}",
         w,
         h,
         colors.len(),
-        col_dimension
+        direction,
+        softness
     );
 
-            for (i, color) in colors.into_iter().enumerate() {
-                let pos_x: u32 = (i * col_dimension) as u32;
-                info!("Loop col color - pos_x = {} - color = {:?}", pos_x, color);
-
-                let filter = ImageBuffer::from_pixel(pos_x, h, color);
-                let filter_dynamic = DynamicImage::ImageRgba8(filter);
-
-                //And then apply the overlay
-                imageops::overlay(img, &filter_dynamic, pos_x as i64, 0);
-            }
-        },
-        GradientDirection::HORIZONTAL => {
-            let row_dimension: usize = h as usize / colors.len();
-            info!(
-                "Image dimension = {}*{} - {} colors to be apply - {}px by horizontal band",
-                w,
-                h,
-                colors.len(),
-                row_dimension
-            );
-
-            for (i, color) in colors.into_iter().enumerate() {
-                let pos_y: u32 = (i * row_dimension) as u32;
-                info!("Loop col color - pos_x = {} - color = {:?}", pos_y, color);
-
-                let filter = ImageBuffer::from_pixel(pos_y, h, color);
-                let filter_dynamic = DynamicImage::ImageRgba8(filter);
-
-                //And then apply the overlay
-                imageops::overlay(img, &filter_dynamic, pos_y as i64, 0);
-            }
-        }
-    }
-
-
-    Ok(img.to_owned())
+    filter_base(img, blend_mode, |x, y| match direction {
+        GradientDirection::VERTICAL => band_color_at(&colors, x, w, softness),
+        GradientDirection::HORIZONTAL => band_color_at(&colors, y, h, softness),
+    })
 }
@@ -0,0 +1,104 @@
+use image::{imageops, DynamicImage, GenericImageView, RgbaImage};
+use imageproc::geometric_transformations;
+use log::*;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use super::{image_filters::ColorRgba, ErrorCode, ProcessingError};
+
+/// Sampling used by the free rotation
+#[wasm_bindgen]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum Interpolation {
+    NEAREST,
+    #[default]
+    BILINEAR,
+    BICUBIC,
+}
+
+impl From<Interpolation> for geometric_transformations::Interpolation {
+    fn from(interpolation: Interpolation) -> Self {
+        match interpolation {
+            Interpolation::NEAREST => geometric_transformations::Interpolation::Nearest,
+            Interpolation::BILINEAR => geometric_transformations::Interpolation::Bilinear,
+            Interpolation::BICUBIC => geometric_transformations::Interpolation::Bicubic,
+        }
+    }
+}
+
+/// Keep the `width` * `height` area starting at (x, y), clamped to the image
+pub fn crop(
+    img: &DynamicImage,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<DynamicImage, ProcessingError> {
+    let (w, h) = img.dimensions();
+    let clamped_width = width.min(w.saturating_sub(x));
+    let clamped_height = height.min(h.saturating_sub(y));
+    if clamped_width == 0 || clamped_height == 0 {
+        error!(
+            "Crop area {}x{} at ({}, {}) is outside the {}x{} image",
+            width, height, x, y, w, h
+        );
+        return Err(ProcessingError::from(ErrorCode::InvalidCrop)
+            .with_detail("x", x)
+            .with_detail("y", y)
+            .with_detail("width", width)
+            .with_detail("height", height)
+            .with_detail("image_width", w)
+            .with_detail("image_height", h));
+    }
+    Ok(img.crop_imm(x, y, clamped_width, clamped_height))
+}
+
+/// Rotate by `angle` degrees clockwise around the centre of the image
+/// The uncovered area is filled with `fill`, `expand` grows the canvas so no corner is cut
+pub fn rotate(
+    img: &DynamicImage,
+    angle: f32,
+    interpolation: Interpolation,
+    fill: ColorRgba,
+    expand: bool,
+) -> DynamicImage {
+    info!(
+        "Rotate {}° ({:?}) - expand = {}",
+        angle, interpolation, expand
+    );
+    let base = img.to_rgba8();
+    if !expand {
+        return DynamicImage::ImageRgba8(geometric_transformations::rotate_about_center(
+            &base,
+            angle.to_radians(),
+            interpolation.into(),
+            fill.into(),
+        ));
+    }
+
+    //Bounding box of the rotated image, the small margin absorbs the float error on right angles
+    let (w, h) = base.dimensions();
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (sin, cos) = (sin.abs(), cos.abs());
+    let new_w = ((w as f32 * cos + h as f32 * sin) - 0.001).ceil().max(1.0) as u32;
+    let new_h = ((w as f32 * sin + h as f32 * cos) - 0.001).ceil().max(1.0) as u32;
+
+    //Rotate on a canvas big enough for both, then keep the bounding box
+    let mut canvas = RgbaImage::from_pixel(new_w.max(w), new_h.max(h), fill.into());
+    let (cw, ch) = canvas.dimensions();
+    imageops::replace(
+        &mut canvas,
+        &base,
+        ((cw - w) / 2) as i64,
+        ((ch - h) / 2) as i64,
+    );
+    let rotated = geometric_transformations::rotate_about_center(
+        &canvas,
+        angle.to_radians(),
+        interpolation.into(),
+        fill.into(),
+    );
+    DynamicImage::ImageRgba8(
+        imageops::crop_imm(&rotated, (cw - new_w) / 2, (ch - new_h) / 2, new_w, new_h).to_image(),
+    )
+}
@@ -0,0 +1,174 @@
+use super::{
+    image_filters::{self, ColorRgba},
+    BlendMode,
+};
+use image::{Rgba, RgbaImage};
+use serde::Deserialize;
+use std::f32::consts::PI;
+use wasm_bindgen::prelude::*;
+
+/// Shape of the gradient
+#[wasm_bindgen]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum GradientType {
+    #[default]
+    LINEAR,
+    RADIAL,
+    CONIC,
+}
+
+/// Color at a position of the gradient, from 0 (start) to 1 (end)
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, Deserialize)]
+pub struct GradientStop {
+    pub position: f32,
+    pub color: ColorRgba,
+}
+
+#[wasm_bindgen]
+impl GradientStop {
+    #[wasm_bindgen(constructor)]
+    pub fn new(position: f32, color: ColorRgba) -> GradientStop {
+        GradientStop {
+            position: position.clamp(0.0, 1.0),
+            color,
+        }
+    }
+}
+
+/// Gradient with any number of color stops
+///     - LINEAR : along `angle` (degrees, 0 = left to right, 90 = top to bottom)
+///     - RADIAL : from the centre to the farthest corner
+///     - CONIC : around the centre, starting at `angle`
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Gradient {
+    kind: GradientType,
+    angle: f32,
+    //Centre relative to the image size, (0.5, 0.5) is the middle
+    center_x: f32,
+    center_y: f32,
+    stops: Vec<GradientStop>,
+    blend_mode: BlendMode,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self {
+            kind: GradientType::LINEAR,
+            angle: 0.0,
+            center_x: 0.5,
+            center_y: 0.5,
+            stops: Vec::new(),
+            blend_mode: BlendMode::NORMAL,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Gradient {
+    #[wasm_bindgen(constructor)]
+    pub fn new(kind: GradientType) -> Gradient {
+        Gradient {
+            kind,
+            ..Gradient::default()
+        }
+    }
+
+    pub fn add_stop(&mut self, position: f32, color: ColorRgba) {
+        self.stops.push(GradientStop::new(position, color));
+    }
+
+    pub fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    pub fn set_center(&mut self, center_x: f32, center_y: f32) {
+        self.center_x = center_x;
+        self.center_y = center_y;
+    }
+
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    pub fn get_blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    pub fn stops_count(&self) -> usize {
+        self.stops.len()
+    }
+}
+
+impl Gradient {
+    /// Position of the pixel in the gradient, between 0 and 1
+    fn position_at(&self, x: u32, y: u32, w: u32, h: u32) -> f32 {
+        let (w, h) = (w as f32, h as f32);
+        let dx = x as f32 + 0.5 - self.center_x * w;
+        let dy = y as f32 + 0.5 - self.center_y * h;
+        let angle = self.angle.to_radians();
+
+        let t = match self.kind {
+            GradientType::LINEAR => {
+                //Project on the gradient line, scaled so that the corners are at 0 and 1
+                let (sin, cos) = angle.sin_cos();
+                let half_length = (w * cos.abs() + h * sin.abs()) / 2.0;
+                if half_length <= 0.0 {
+                    0.0
+                } else {
+                    ((dx * cos + dy * sin) / half_length + 1.0) / 2.0
+                }
+            }
+            GradientType::RADIAL => {
+                let cx = self.center_x * w;
+                let cy = self.center_y * h;
+                let radius = [(0.0, 0.0), (w, 0.0), (0.0, h), (w, h)]
+                    .iter()
+                    .map(|(px, py)| ((px - cx).powi(2) + (py - cy).powi(2)).sqrt())
+                    .fold(0.0, f32::max);
+                if radius <= 0.0 {
+                    0.0
+                } else {
+                    (dx * dx + dy * dy).sqrt() / radius
+                }
+            }
+            GradientType::CONIC => (dy.atan2(dx) - angle).rem_euclid(2.0 * PI) / (2.0 * PI),
+        };
+        t.clamp(0.0, 1.0)
+    }
+
+    /// Interpolated color between the surrounding stops
+    fn color_at(stops: &[GradientStop], t: f32) -> Rgba<u8> {
+        let first = stops[0];
+        let last = stops[stops.len() - 1];
+        if t <= first.position {
+            return first.color.into();
+        }
+        if t >= last.position {
+            return last.color.into();
+        }
+
+        let end = stops.iter().position(|s| s.position >= t).unwrap_or(stops.len() - 1);
+        let (from, to) = (stops[end - 1], stops[end]);
+        let range = to.position - from.position;
+        let ratio = if range <= 0.0 { 1.0 } else { (t - from.position) / range };
+
+        image_filters::mix_colors(from.color.into(), to.color.into(), ratio)
+    }
+
+    /// Draw the gradient on a new layer, None if there is no stop
+    pub fn render(&self, w: u32, h: u32) -> Option<RgbaImage> {
+        if self.stops.is_empty() {
+            return None;
+        }
+        let mut stops = self.stops.clone();
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+
+        Some(RgbaImage::from_fn(w, h, |x, y| {
+            Gradient::color_at(&stops, self.position_at(x, y, w, h))
+        }))
+    }
+}
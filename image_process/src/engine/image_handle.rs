@@ -0,0 +1,326 @@
+use super::{
+    image_orientation,
+    image_filters::{ColorRgba, FilterPixelType, GradientDirection},
+    image_processing_result::ImageDimension,
+    Animation, BlendMode, ColorPalette, Curves, EmbeddedMetadata, ProcessingError, Gradient, History, ImageParameters, ImageProcess, ImageProcessingResult, Operation,
+    ImageInput, InputType, Levels, Lut3D, LutInterpolation, OutputOptions, Pipeline, ResizeOptions, Interpolation,
+};
+use image::{DynamicImage, GenericImageView};
+use log::*;
+use wasm_bindgen::prelude::*;
+
+/// Decoded image kept in WASM memory between calls
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct ImageHandle {
+    image: DynamicImage,
+    history: History,
+    //EXIF / ICC / XMP of the input, written on export when the output options keep them
+    metadata: EmbeddedMetadata,
+    //Frames of an animated input, the operations of the history are replayed on them on export
+    animation: Option<Animation>,
+}
+
+#[wasm_bindgen]
+impl ImageHandle {
+    /// Decode the image (base64, data URL or bytes) once
+    /// `auto_orient` applies the EXIF orientation, `set_auto_orient` gives the default
+    #[wasm_bindgen(constructor)]
+    pub fn new(input: ImageInput, auto_orient: Option<bool>) -> Result<ImageHandle, JsValue> {
+        Ok(ImageHandle::decode(input, auto_orient)?)
+    }
+
+    /// Independent copy, useful to keep an untouched original for previews
+    pub fn duplicate(&self) -> ImageHandle {
+        self.clone()
+    }
+
+    /// 1 for a still image, the edits are shown on the first frame
+    pub fn get_frame_count(&self) -> u32 {
+        self.animation
+            .as_ref()
+            .map_or(1, |animation| animation.frame_count())
+    }
+
+    pub fn get_dimension(&self) -> ImageDimension {
+        let (width, height) = self.image.dimensions();
+        ImageDimension::new(width, height)
+    }
+
+    pub fn apply_parameters(&mut self, params: ImageParameters) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Parameters(params))?)
+    }
+
+    pub fn apply_curves(&mut self, curves: &Curves) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Curves(curves.clone()))?)
+    }
+
+    pub fn apply_levels(&mut self, levels: Levels) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Levels(levels))?)
+    }
+
+    pub fn apply_lut(
+        &mut self,
+        lut: &Lut3D,
+        interpolation: Option<LutInterpolation>,
+        intensity: Option<f32>,
+    ) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::lut(lut, interpolation, intensity))?)
+    }
+
+    pub fn apply_sobel(&mut self) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Sobel)?)
+    }
+
+    /// Named preset, see `list_presets`, `intensity` from 0 to 1 (the default)
+    pub fn apply_preset(&mut self, name: &str, intensity: Option<f32>) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::preset(name, intensity))?)
+    }
+
+    pub fn apply_band_color(
+        &mut self,
+        direction: GradientDirection,
+        palette: Option<ColorPalette>,
+        softness: Option<f32>,
+        blend_mode: Option<BlendMode>,
+    ) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::band_color(
+            palette, direction, softness, blend_mode,
+        ))?)
+    }
+
+    pub fn apply_gradient(
+        &mut self,
+        from: ColorRgba,
+        to: ColorRgba,
+        direction: GradientDirection,
+        blend_mode: Option<BlendMode>,
+    ) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Gradient {
+            from,
+            to,
+            direction,
+            blend_mode: blend_mode.unwrap_or_default(),
+        })?)
+    }
+
+    pub fn apply_custom_gradient(&mut self, gradient: &Gradient) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::CustomGradient(gradient.clone()))?)
+    }
+
+    pub fn apply_pixel(
+        &mut self,
+        pixel_type: FilterPixelType,
+        color: ColorRgba,
+        spacing: Option<u32>,
+        thickness: Option<u32>,
+        blend_mode: Option<BlendMode>,
+    ) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::pixel(
+            pixel_type, color, spacing, thickness, blend_mode,
+        ))?)
+    }
+
+    pub fn resize(
+        &mut self,
+        width: u32,
+        height: u32,
+        options: Option<ResizeOptions>,
+    ) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Resize {
+            width,
+            height,
+            options: options.unwrap_or_default(),
+        })?)
+    }
+
+    pub fn crop(&mut self, x: u32, y: u32, width: u32, height: u32) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Crop {
+            x,
+            y,
+            width,
+            height,
+        })?)
+    }
+
+    pub fn rotate90(&mut self) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Rotate90)?)
+    }
+
+    pub fn rotate180(&mut self) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Rotate180)?)
+    }
+
+    pub fn rotate270(&mut self) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::Rotate270)?)
+    }
+
+    pub fn flip_horizontal(&mut self) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::FlipHorizontal)?)
+    }
+
+    pub fn flip_vertical(&mut self) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::FlipVertical)?)
+    }
+
+    /// Rotate by `angle` degrees clockwise, `expand` grows the canvas to keep the corners
+    pub fn rotate(
+        &mut self,
+        angle: f32,
+        interpolation: Option<Interpolation>,
+        fill: Option<ColorRgba>,
+        expand: Option<bool>,
+    ) -> Result<(), JsValue> {
+        Ok(self.apply(&Operation::rotate(angle, interpolation, fill, expand))?)
+    }
+
+    pub fn apply_pipeline(&mut self, pipeline: &Pipeline) -> Result<(), JsValue> {
+        for operation in pipeline.operations() {
+            self.apply(operation)?;
+        }
+        Ok(())
+    }
+
+    /// Cancel the last operation, return false if there is nothing to undo
+    pub fn undo(&mut self) -> Result<bool, JsValue> {
+        match self.history.undo()? {
+            Some(image) => {
+                self.image = image;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Apply again the last undone operation, return false if there is nothing to redo
+    pub fn redo(&mut self) -> Result<bool, JsValue> {
+        match self.history.redo(self.image.clone())? {
+            Some(image) => {
+                self.image = image;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Names of the operations currently applied, in order, "reset" for `reset_to_original`
+    pub fn history(&self) -> Vec<String> {
+        self.history
+            .names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Come back to the decoded image, can be undone
+    pub fn reset_to_original(&mut self) {
+        self.image = self.history.reset();
+    }
+
+    /// Keep a copy of the image every `interval` operations to speed up undo (0 to disable)
+    /// Lower values use more memory
+    pub fn set_snapshot_interval(&mut self, interval: usize) {
+        self.history.set_snapshot_interval(interval);
+    }
+
+    /// Copies of the image kept at most by the history, 4 by default (0 to disable)
+    pub fn set_max_snapshots(&mut self, max: usize) {
+        self.history.set_max_snapshots(max);
+    }
+
+    /// Encode the current state of the image
+    pub fn export(&self, output: Option<OutputOptions>) -> Result<ImageProcessingResult, JsValue> {
+        let output = output.unwrap_or_default();
+        if let Some(animation) = &self.animation {
+            if output.keep_animation && output.format.supports_animation() {
+                let operations = self.history.applied();
+                let animation = animation.map_frames(|img| {
+                    operations
+                        .iter()
+                        .try_fold(img, |img, operation| operation.apply(img))
+                })?;
+                return Ok(ImageProcessingResult::new(
+                    output.encode_animation(&animation, &self.metadata)?,
+                    output.format,
+                ));
+            }
+        }
+        Ok(ImageProcessingResult::new(
+            output.encode_with_metadata(&self.image, &self.metadata)?,
+            output.format,
+        ))
+    }
+
+    /// Encode the image with the parameters applied, without changing the handle
+    pub fn preview_parameters(
+        &self,
+        params: ImageParameters,
+        output: Option<OutputOptions>,
+    ) -> Result<ImageProcessingResult, JsValue> {
+        let output = output.unwrap_or_default();
+        Ok(ImageProcessingResult::new(
+            output.encode_with_metadata(&params.apply_filter(self.image.clone()), &self.metadata)?,
+            output.format,
+        ))
+    }
+}
+
+impl ImageHandle {
+    /// Decode any input once, with its frames and metadata
+    pub fn decode<T: InputType>(
+        input: T,
+        auto_orient: Option<bool>,
+    ) -> Result<ImageHandle, ProcessingError> {
+        let process = ImageProcess::new(input)?
+            .with_auto_orient(auto_orient.unwrap_or_else(image_orientation::auto_orient));
+        let handle = match process.get_animation()? {
+            Some(animation) => ImageHandle::from_animation(animation),
+            None => ImageHandle::from_image(process.get_dynamic_image()?),
+        };
+        Ok(handle.with_metadata(process.get_embedded_metadata()))
+    }
+
+    pub fn from_image(image: DynamicImage) -> ImageHandle {
+        trace!("New ImageHandle instance");
+        ImageHandle {
+            history: History::new(image.clone()),
+            image,
+            metadata: EmbeddedMetadata::default(),
+            animation: None,
+        }
+    }
+
+    /// Edit the first frame, every frame is processed on export
+    pub fn from_animation(animation: Animation) -> ImageHandle {
+        let image = animation.frames()[0].image.clone();
+        ImageHandle {
+            animation: Some(animation),
+            ..ImageHandle::from_image(image)
+        }
+    }
+
+    pub fn with_metadata(mut self, metadata: EmbeddedMetadata) -> ImageHandle {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn image(&self) -> &DynamicImage {
+        &self.image
+    }
+
+    /// Apply the operation on the image held by the handle and store it in the history
+    pub fn apply(&mut self, operation: &Operation) -> Result<(), ProcessingError> {
+        self.image = operation.apply(self.image.clone())?;
+        self.history.push(operation.clone(), &self.image);
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use super::{ProcessingError, Operation};
+use image::DynamicImage;
+use log::*;
+
+/// Entry of the history
+#[derive(Debug, Clone)]
+enum Step {
+    Operation(Operation),
+    //Back to the original image, kept as a step so it can be undone
+    Reset,
+}
+
+impl Step {
+    fn name(&self) -> &'static str {
+        match self {
+            Step::Operation(operation) => operation.name(),
+            Step::Reset => "reset",
+        }
+    }
+}
+
+/// Operations applied on an image, to undo / redo them
+/// The image is rebuilt by replaying the operations from the nearest snapshot
+#[derive(Debug, Clone)]
+pub struct History {
+    original: DynamicImage,
+    steps: Vec<Step>,
+    //Number of steps currently applied, the following ones can be redone
+    position: usize,
+    //Images after N steps, sorted by N
+    snapshots: Vec<(usize, DynamicImage)>,
+    //Take a snapshot every N steps, 0 to disable them
+    snapshot_interval: usize,
+    //Snapshots kept at most, the oldest one is dropped first
+    max_snapshots: usize,
+}
+
+impl History {
+    pub const DEFAULT_SNAPSHOT_INTERVAL: usize = 5;
+    pub const DEFAULT_MAX_SNAPSHOTS: usize = 4;
+
+    pub fn new(original: DynamicImage) -> History {
+        History {
+            original,
+            steps: Vec::new(),
+            position: 0,
+            snapshots: Vec::new(),
+            snapshot_interval: History::DEFAULT_SNAPSHOT_INTERVAL,
+            max_snapshots: History::DEFAULT_MAX_SNAPSHOTS,
+        }
+    }
+
+    pub fn original(&self) -> &DynamicImage {
+        &self.original
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn snapshot_count(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position < self.steps.len()
+    }
+
+    /// Names of the steps currently applied, resets included
+    pub fn names(&self) -> Vec<&'static str> {
+        self.steps[..self.position]
+            .iter()
+            .map(|step| step.name())
+            .collect()
+    }
+
+    /// Operations giving the current image from the original one, since the last reset
+    pub fn applied(&self) -> Vec<&Operation> {
+        self.steps[self.last_reset()..self.position]
+            .iter()
+            .filter_map(|step| match step {
+                Step::Operation(operation) => Some(operation),
+                Step::Reset => None,
+            })
+            .collect()
+    }
+
+    pub fn set_snapshot_interval(&mut self, interval: usize) {
+        self.snapshot_interval = interval;
+        if interval == 0 {
+            self.snapshots.clear();
+        } else {
+            self.snapshots.retain(|(index, _)| index.is_multiple_of(interval));
+        }
+    }
+
+    /// 0 disables the snapshots, every undo then replays from the original image
+    pub fn set_max_snapshots(&mut self, max: usize) {
+        self.max_snapshots = max;
+        self.evict_snapshots();
+    }
+
+    /// Store an operation already applied on `image`, the redo stack is dropped
+    pub fn push(&mut self, operation: Operation, image: &DynamicImage) {
+        self.push_step(Step::Operation(operation), image);
+    }
+
+    /// Come back to the original image, as a step that can be undone
+    pub fn reset(&mut self) -> DynamicImage {
+        //Nothing to reset since the original or the last reset
+        if self.last_reset() < self.position {
+            let original = self.original.clone();
+            self.push_step(Step::Reset, &original);
+        }
+        self.original.clone()
+    }
+
+    /// Step back once and return the rebuilt image
+    pub fn undo(&mut self) -> Result<Option<DynamicImage>, ProcessingError> {
+        if !self.can_undo() {
+            return Ok(None);
+        }
+        self.position -= 1;
+        Ok(Some(self.rebuild()?))
+    }
+
+    /// Apply again the next undone step on `image`
+    pub fn redo(&mut self, image: DynamicImage) -> Result<Option<DynamicImage>, ProcessingError> {
+        if !self.can_redo() {
+            return Ok(None);
+        }
+        let image = self.apply_step(self.position, image)?;
+        self.position += 1;
+        self.snapshot_if_needed(&image);
+        Ok(Some(image))
+    }
+
+    fn push_step(&mut self, step: Step, image: &DynamicImage) {
+        let position = self.position;
+        self.steps.truncate(position);
+        self.snapshots.retain(|(index, _)| *index <= position);
+
+        self.steps.push(step);
+        self.position += 1;
+        self.snapshot_if_needed(image);
+    }
+
+    fn apply_step(&self, index: usize, image: DynamicImage) -> Result<DynamicImage, ProcessingError> {
+        match &self.steps[index] {
+            Step::Operation(operation) => operation.apply(image),
+            Step::Reset => Ok(self.original.clone()),
+        }
+    }
+
+    /// Number of steps up to the last applied reset, 0 without reset
+    fn last_reset(&self) -> usize {
+        self.steps[..self.position]
+            .iter()
+            .rposition(|step| matches!(step, Step::Reset))
+            .map_or(0, |index| index + 1)
+    }
+
+    fn snapshot_if_needed(&mut self, image: &DynamicImage) {
+        let position = self.position;
+        if self.snapshot_interval == 0 || !position.is_multiple_of(self.snapshot_interval) {
+            return;
+        }
+        if !self.snapshots.iter().any(|(index, _)| *index == position) {
+            trace!("History snapshot after {} steps", position);
+            self.snapshots.push((position, image.clone()));
+            self.evict_snapshots();
+        }
+    }
+
+    //A full copy of the image per snapshot, keep the memory bounded
+    fn evict_snapshots(&mut self) {
+        if self.snapshots.len() > self.max_snapshots {
+            let extra = self.snapshots.len() - self.max_snapshots;
+            trace!("Drop the {} oldest history snapshots", extra);
+            self.snapshots.drain(..extra);
+        }
+    }
+
+    /// Replay the steps from the last reset or the nearest snapshot up to the current position
+    fn rebuild(&self) -> Result<DynamicImage, ProcessingError> {
+        let last_reset = self.last_reset();
+        let (start, base) = self
+            .snapshots
+            .iter()
+            .rev()
+            .find(|(index, _)| *index <= self.position && *index >= last_reset)
+            .map(|(index, image)| (*index, image))
+            .unwrap_or((last_reset, &self.original));
+
+        trace!("Rebuild image from step {} to {}", start, self.position);
+        (start..self.position).try_fold(base.clone(), |img, index| self.apply_step(index, img))
+    }
+}
@@ -0,0 +1,182 @@
+use super::{image_processing::InputType, ErrorCode, ErrorStage, ProcessingError};
+use js_sys::Uint8Array;
+use log::*;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[wasm_bindgen]
+extern "C" {
+    /// Image given by the front : base64, data URL or raw bytes
+    #[wasm_bindgen(typescript_type = "string | Uint8Array")]
+    pub type ImageInput;
+}
+
+//InputType from Typescript, string or Uint8Array
+impl InputType for ImageInput {
+    fn to_byte(&self) -> Result<Vec<u8>, ProcessingError> {
+        let value: &JsValue = self.as_ref();
+        if let Some(text) = value.as_string() {
+            text.to_byte()
+        } else if let Some(bytes) = value.dyn_ref::<Uint8Array>() {
+            trace!("Copy Uint8Array input ({} bytes)", bytes.length());
+            Ok(bytes.to_vec())
+        } else {
+            error!("Image input is neither a string nor a Uint8Array");
+            Err(ProcessingError::from(ErrorCode::InvalidParsing)
+                .with_cause("input is neither a string nor a Uint8Array"))
+        }
+    }
+}
+
+impl ImageInput {
+    /// Size of the encoded image, the base64 is not decoded
+    pub fn encoded_length(&self) -> Result<usize, ProcessingError> {
+        let value: &JsValue = self.as_ref();
+        if let Some(text) = value.as_string() {
+            encoded_length(&text)
+        } else if let Some(bytes) = value.dyn_ref::<Uint8Array>() {
+            Ok(bytes.length() as usize)
+        } else {
+            error!("Image input is neither a string nor a Uint8Array");
+            Err(ProcessingError::from(ErrorCode::InvalidParsing)
+                .with_cause("input is neither a string nor a Uint8Array"))
+        }
+    }
+}
+
+/// Data URL split in its parts, ex : `data:image/jpeg;base64,/9j/4AAQ...`
+#[derive(Debug, PartialEq, Eq)]
+pub struct DataUrl<'a> {
+    pub mime_type: &'a str,
+    pub is_base64: bool,
+    pub data: &'a str,
+}
+
+impl<'a> DataUrl<'a> {
+    /// None if the input is not a data URL
+    pub fn parse(input: &'a str) -> Option<DataUrl<'a>> {
+        let input = input.trim_start();
+        if !input.get(..5)?.eq_ignore_ascii_case("data:") {
+            return None;
+        }
+        let (header, data) = input[5..].split_once(',')?;
+        let mut parts = header.split(';');
+        let mime_type = parts.next().unwrap_or("").trim();
+        let is_base64 = parts.any(|p| p.trim().eq_ignore_ascii_case("base64"));
+
+        Some(DataUrl {
+            mime_type,
+            is_base64,
+            data,
+        })
+    }
+
+    pub fn decode(&self) -> Result<Vec<u8>, ProcessingError> {
+        if self.is_base64 {
+            decode_base64(self.data)
+        } else {
+            decode_percent(self.data)
+        }
+    }
+}
+
+/// Remove the data URL header if any, keep only the payload
+pub fn strip_data_url(input: &str) -> &str {
+    match DataUrl::parse(input) {
+        Some(data_url) => data_url.data,
+        None => input,
+    }
+}
+
+/// Decode a string image : data URL (base64 or percent-encoded) or bare base64
+pub fn decode_string(input: &str) -> Result<Vec<u8>, ProcessingError> {
+    match DataUrl::parse(input) {
+        Some(data_url) => {
+            trace!("Decode data URL ({})", data_url.mime_type);
+            data_url.decode()
+        }
+        None => decode_base64(input),
+    }
+}
+
+/// Bytes size of a base64 string or data URL, without decoding it
+/// Formula : x = floor(n * 3 / 4)
+///     1. x is the size of a file in bytes
+///     2. n is the number of base64 digits, without the '=' padding and the whitespaces
+pub fn encoded_length(input: &str) -> Result<usize, ProcessingError> {
+    let data = match DataUrl::parse(input) {
+        Some(data_url) if !data_url.is_base64 => return Ok(decode_percent(data_url.data)?.len()),
+        Some(data_url) => data_url.data,
+        None => input,
+    };
+    let mut digits = 0;
+    for (position, b) in data.bytes().enumerate() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'+' | b'/' | b'-' | b'_' => digits += 1,
+            b'=' => {}
+            b if b.is_ascii_whitespace() => {}
+            //Neither standard nor URL-safe base64
+            _ => {
+                error!("Invalid base64 character at {}", position);
+                return Err(ProcessingError::from(ErrorCode::InvalidParsing)
+                    .with_detail("position", position)
+                    .with_detail("character", char::from(b).escape_default()));
+            }
+        }
+    }
+    trace!("base64 length = {} digits", digits);
+
+    if digits == 0 {
+        return Err(ErrorCode::ImageEmpty.into());
+    }
+    Ok(digits * 3 / 4)
+}
+
+/// Decode standard or URL-safe base64, padding and whitespace are optional
+pub fn decode_base64(input: &str) -> Result<Vec<u8>, ProcessingError> {
+    let clean: String = input
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && *c != '=')
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+
+    base64::decode_config(&clean, base64::STANDARD_NO_PAD).map_err(|e| {
+        error!("Failed base64::decode() : {}", e);
+        ProcessingError::new(ErrorCode::UnableToDecode, ErrorStage::INPUT).with_cause(e)
+    })
+}
+
+/// Decode a percent-encoded payload (`%89PNG...`)
+pub fn decode_percent(input: &str) -> Result<Vec<u8>, ProcessingError> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            //from_str_radix alone would accept a sign, like %+5
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match hex {
+                Some(byte) => decoded.push(byte),
+                None => {
+                    error!("Invalid percent-encoding at position {}", i);
+                    return Err(ProcessingError::new(ErrorCode::UnableToDecode, ErrorStage::INPUT)
+                        .with_cause("invalid percent-encoding")
+                        .with_detail("position", i));
+                }
+            }
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(decoded)
+}
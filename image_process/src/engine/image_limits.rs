@@ -0,0 +1,150 @@
+use image::io::Limits;
+use image::ImageFormat;
+use log::*;
+use serde::Deserialize;
+use std::sync::RwLock;
+use wasm_bindgen::prelude::*;
+
+use super::{image_metadata, ErrorCode, ProcessingError};
+
+//Applied to every decoded image, see `set_decode_limits`
+static DECODE_LIMITS: RwLock<DecodeLimits> = RwLock::new(DecodeLimits::DEFAULT);
+
+/// Checked before decoding, so a small file declaring a huge image is rejected
+/// without allocating its pixels. A 0 field means no limit
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct DecodeLimits {
+    pub max_width: u32,
+    pub max_height: u32,
+    //Width * height
+    pub max_pixels: u32,
+    //Bytes of the decoded pixels
+    pub max_alloc_bytes: u32,
+    //Frames of an animated GIF, APNG or WebP
+    pub max_frames: u32,
+}
+
+#[wasm_bindgen]
+impl DecodeLimits {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        max_width: Option<u32>,
+        max_height: Option<u32>,
+        max_pixels: Option<u32>,
+        max_alloc_bytes: Option<u32>,
+        max_frames: Option<u32>,
+    ) -> DecodeLimits {
+        let default = DecodeLimits::default();
+        DecodeLimits {
+            max_width: max_width.unwrap_or(default.max_width),
+            max_height: max_height.unwrap_or(default.max_height),
+            max_pixels: max_pixels.unwrap_or(default.max_pixels),
+            max_alloc_bytes: max_alloc_bytes.unwrap_or(default.max_alloc_bytes),
+            max_frames: max_frames.unwrap_or(default.max_frames),
+        }
+    }
+
+    /// No limit at all, only for trusted inputs
+    pub fn unlimited() -> DecodeLimits {
+        DecodeLimits {
+            max_width: 0,
+            max_height: 0,
+            max_pixels: 0,
+            max_alloc_bytes: 0,
+            max_frames: 0,
+        }
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl DecodeLimits {
+    //16384 px per side, 64 megapixels, 512 MiB of pixels (the image crate default), 1000 frames
+    const DEFAULT: DecodeLimits = DecodeLimits {
+        max_width: 16_384,
+        max_height: 16_384,
+        max_pixels: 64 * 1024 * 1024,
+        max_alloc_bytes: 512 * 1024 * 1024,
+        max_frames: 1_000,
+    };
+
+    /// Check the size read from the header against the limits
+    pub fn check(
+        &self,
+        format: ImageFormat,
+        width: u32,
+        height: u32,
+        bytes_per_pixel: u8,
+        bytes: &[u8],
+    ) -> Result<(), ProcessingError> {
+        let pixels = width as u64 * height as u64;
+        let frames = image_metadata::frame_count(format, bytes) as u64;
+        exceeds("width", width as u64, self.max_width)?;
+        exceeds("height", height as u64, self.max_height)?;
+        exceeds("pixels", pixels, self.max_pixels)?;
+        exceeds("frames", frames, self.max_frames)?;
+        //Animations are decoded as RGBA, one canvas per frame
+        let alloc_bytes = if frames > 1 {
+            pixels * 4 * frames
+        } else {
+            pixels * bytes_per_pixel as u64
+        };
+        exceeds("alloc_bytes", alloc_bytes, self.max_alloc_bytes)?;
+        Ok(())
+    }
+
+    /// Check the size of an RGBA image built from decoded ones, like a contact sheet
+    /// Without limit, each side still has to fit in an u32
+    pub fn check_canvas(&self, width: u64, height: u64) -> Result<(u32, u32), ProcessingError> {
+        let side = |max: u32| if max == 0 { u32::MAX } else { max };
+        let pixels = width.saturating_mul(height);
+        exceeds("width", width, side(self.max_width))?;
+        exceeds("height", height, side(self.max_height))?;
+        exceeds("pixels", pixels, self.max_pixels)?;
+        exceeds("alloc_bytes", pixels.saturating_mul(4), self.max_alloc_bytes)?;
+        Ok((width as u32, height as u32))
+    }
+
+    /// Limits given to the decoder of the image crate
+    pub fn image_limits(&self) -> Limits {
+        let some = |limit: u32| if limit == 0 { None } else { Some(limit) };
+        let mut limits = Limits::no_limits();
+        limits.max_image_width = some(self.max_width);
+        limits.max_image_height = some(self.max_height);
+        limits.max_alloc = some(self.max_alloc_bytes).map(u64::from);
+        limits
+    }
+}
+
+//Error when `value` is above `max`, with both in the details
+fn exceeds(limit: &'static str, value: u64, max: u32) -> Result<(), ProcessingError> {
+    if max == 0 || value <= max as u64 {
+        return Ok(());
+    }
+    error!("Decoding limit exceeded : {} = {} (max {})", limit, value, max);
+    Err(ProcessingError::from(ErrorCode::LimitExceeded)
+        .with_detail("limit", limit)
+        .with_detail("requested", value)
+        .with_detail("max", max))
+}
+
+/// Replace the limits applied to the next decoded images
+pub fn set_decode_limits(limits: DecodeLimits) {
+    match DECODE_LIMITS.write() {
+        Ok(mut current) => *current = limits,
+        Err(poisoned) => *poisoned.into_inner() = limits,
+    }
+}
+
+pub fn decode_limits() -> DecodeLimits {
+    match DECODE_LIMITS.read() {
+        Ok(limits) => *limits,
+        Err(poisoned) => *poisoned.into_inner(),
+    }
+}
@@ -0,0 +1,358 @@
+use image::{DynamicImage, GenericImageView};
+use log::*;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+use super::{image_adjustments, ErrorCode, ImageInput, ImageProcess, ProcessingError};
+
+//Sizes accepted for a 3D LUT, 65 is the largest size of the usual .cube files
+//and a Hald CLUT of level 8 (64) : 65^3 entries are already 3.3 MB of floats
+const MIN_LUT_SIZE: usize = 2;
+const MAX_LUT_SIZE: usize = 65;
+
+/// Interpolation between the entries of a 3D LUT
+#[wasm_bindgen]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum LutInterpolation {
+    //8 surrounding entries
+    TRILINEAR,
+    //4 surrounding entries, smoother on the gray axis
+    #[default]
+    TETRAHEDRAL,
+}
+
+/// 3D color lookup table, loaded from an Adobe / Resolve `.cube` file or a Hald CLUT image
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Lut3D {
+    title: Option<String>,
+    //Entries per side
+    size: usize,
+    //Red changes the fastest, then green, then blue
+    table: Vec<[f32; 3]>,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+}
+
+#[wasm_bindgen]
+impl Lut3D {
+    /// Parse the content of a `.cube` file
+    pub fn from_cube(text: &str) -> Result<Lut3D, JsValue> {
+        Ok(Lut3D::parse_cube(text)?)
+    }
+
+    /// Decode a Hald CLUT image (base64, data URL or bytes)
+    pub fn from_hald(input: ImageInput) -> Result<Lut3D, JsValue> {
+        let img = ImageProcess::new(input)?.get_dynamic_image()?;
+        Ok(Lut3D::from_hald_image(&img)?)
+    }
+
+    /// Identity LUT, the image is unchanged (except rounding)
+    pub fn identity(size: usize) -> Lut3D {
+        let size = size.clamp(MIN_LUT_SIZE, MAX_LUT_SIZE);
+        let max = (size - 1) as f32;
+        let table = (0..size * size * size)
+            .map(|i| {
+                [
+                    (i % size) as f32 / max,
+                    (i / size % size) as f32 / max,
+                    (i / (size * size)) as f32 / max,
+                ]
+            })
+            .collect();
+        Lut3D {
+            title: None,
+            size,
+            table,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+        }
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_title(&self) -> Option<String> {
+        self.title.clone()
+    }
+}
+
+//The table is too large for the logs
+impl fmt::Debug for Lut3D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lut3D")
+            .field("title", &self.title)
+            .field("size", &self.size)
+            .field("domain_min", &self.domain_min)
+            .field("domain_max", &self.domain_max)
+            .finish()
+    }
+}
+
+impl TryFrom<String> for Lut3D {
+    type Error = ProcessingError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Lut3D::parse_cube(&text)
+    }
+}
+
+impl Lut3D {
+    pub fn parse_cube(text: &str) -> Result<Lut3D, ProcessingError> {
+        let parse_error = |line: usize, cause: &str| {
+            error!("Unable to parse the .cube LUT, line {} : {}", line, cause);
+            ProcessingError::from(ErrorCode::InvalidParsing)
+                .with_cause(cause)
+                .with_detail("format", "cube")
+                .with_detail("line", line)
+        };
+        let parse_floats = |line: usize, values: &[&str]| -> Result<[f32; 3], ProcessingError> {
+            if values.len() != 3 {
+                return Err(parse_error(line, "expected 3 values"));
+            }
+            let mut floats = [0.0; 3];
+            for (float, value) in floats.iter_mut().zip(values) {
+                *float = value
+                    .parse()
+                    .map_err(|_| parse_error(line, "invalid number"))?;
+            }
+            Ok(floats)
+        };
+
+        let mut title = None;
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            let values: Vec<&str> = words.collect();
+            match keyword {
+                "TITLE" => {
+                    let rest = line["TITLE".len()..].trim();
+                    title = Some(rest.trim_matches('"').to_string());
+                }
+                "LUT_3D_SIZE" => {
+                    let n = values
+                        .first()
+                        .and_then(|value| value.parse::<usize>().ok())
+                        .filter(|n| (MIN_LUT_SIZE..=MAX_LUT_SIZE).contains(n))
+                        .ok_or_else(|| parse_error(number, "invalid LUT_3D_SIZE"))?;
+                    //No reserve from the declared size, the table only grows with the lines read
+                    size = Some(n);
+                }
+                "LUT_1D_SIZE" => {
+                    error!("1D LUT are not supported");
+                    return Err(ProcessingError::from(ErrorCode::NotImplemented)
+                        .with_detail("format", "cube")
+                        .with_detail("line", number));
+                }
+                "DOMAIN_MIN" => domain_min = parse_floats(number, &values)?,
+                "DOMAIN_MAX" => domain_max = parse_floats(number, &values)?,
+                //Resolve writes the same range for the three channels
+                "LUT_3D_INPUT_RANGE" => {
+                    if values.len() != 2 {
+                        return Err(parse_error(number, "expected 2 values"));
+                    }
+                    let [min, max, _] = parse_floats(number, &[values[0], values[1], "0"])?;
+                    domain_min = [min; 3];
+                    domain_max = [max; 3];
+                }
+                _ if keyword.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') => {
+                    if size.is_none() {
+                        return Err(parse_error(number, "data before LUT_3D_SIZE"));
+                    }
+                    let mut row = vec![keyword];
+                    row.extend(values);
+                    table.push(parse_floats(number, &row)?);
+                }
+                //Unknown keywords are skipped, like the other readers do
+                _ => trace!("Skip .cube keyword {}", keyword),
+            }
+        }
+
+        let size = size.ok_or_else(|| parse_error(0, "missing LUT_3D_SIZE"))?;
+        if table.len() != size * size * size {
+            error!(
+                "The .cube LUT has {} entries instead of {}",
+                table.len(),
+                size * size * size
+            );
+            return Err(ProcessingError::from(ErrorCode::InvalidParsing)
+                .with_cause("wrong number of entries")
+                .with_detail("format", "cube")
+                .with_detail("entries", table.len())
+                .with_detail("expected", size * size * size));
+        }
+        if (0..3).any(|c| domain_max[c] <= domain_min[c]) {
+            return Err(parse_error(0, "empty domain"));
+        }
+        Ok(Lut3D {
+            title,
+            size,
+            table,
+            domain_min,
+            domain_max,
+        })
+    }
+
+    /// Hald CLUT of level L : square image of L^3 pixels per side, L^2 entries per side
+    pub fn from_hald_image(img: &DynamicImage) -> Result<Lut3D, ProcessingError> {
+        let (width, height) = img.dimensions();
+        let level = (1..=16).find(|level| level * level * level == width);
+        let size = match level {
+            Some(level)
+                if width == height
+                    && (MIN_LUT_SIZE..=MAX_LUT_SIZE).contains(&((level * level) as usize)) =>
+            {
+                (level * level) as usize
+            }
+            _ => {
+                error!("{}x{} is not the size of a Hald CLUT", width, height);
+                return Err(ProcessingError::from(ErrorCode::InvalidParsing)
+                    .with_cause("not a Hald CLUT")
+                    .with_detail("width", width)
+                    .with_detail("height", height));
+            }
+        };
+        let table = img
+            .to_rgb32f()
+            .pixels()
+            .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect();
+        Ok(Lut3D {
+            title: None,
+            size,
+            table,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+        })
+    }
+
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[r + g * self.size + b * self.size * self.size]
+    }
+
+    /// Color of the LUT for an RGB value from 0 to 1
+    pub fn lookup(&self, rgb: [f32; 3], interpolation: LutInterpolation) -> [f32; 3] {
+        let max = (self.size - 1) as f32;
+        let mut base = [0; 3];
+        let mut fraction = [0.0; 3];
+        for c in 0..3 {
+            let range = self.domain_max[c] - self.domain_min[c];
+            let position = ((rgb[c] - self.domain_min[c]) / range).clamp(0.0, 1.0) * max;
+            //The last cell is used for the maximum value
+            base[c] = (position.floor() as usize).min(self.size - 2);
+            fraction[c] = position - base[c] as f32;
+        }
+        let [r, g, b] = base;
+        let [fr, fg, fb] = fraction;
+        let corner = |dr: usize, dg: usize, db: usize| self.entry(r + dr, g + dg, b + db);
+        let mix = |weights: &[(f32, [f32; 3])]| {
+            let mut out = [0.0; 3];
+            for (weight, value) in weights {
+                for c in 0..3 {
+                    out[c] += weight * value[c];
+                }
+            }
+            out
+        };
+
+        match interpolation {
+            LutInterpolation::TRILINEAR => mix(&[
+                ((1.0 - fr) * (1.0 - fg) * (1.0 - fb), corner(0, 0, 0)),
+                (fr * (1.0 - fg) * (1.0 - fb), corner(1, 0, 0)),
+                ((1.0 - fr) * fg * (1.0 - fb), corner(0, 1, 0)),
+                (fr * fg * (1.0 - fb), corner(1, 1, 0)),
+                ((1.0 - fr) * (1.0 - fg) * fb, corner(0, 0, 1)),
+                (fr * (1.0 - fg) * fb, corner(1, 0, 1)),
+                ((1.0 - fr) * fg * fb, corner(0, 1, 1)),
+                (fr * fg * fb, corner(1, 1, 1)),
+            ]),
+            //The cube is split in 6 tetrahedra along its diagonal
+            LutInterpolation::TETRAHEDRAL => {
+                let (c000, c111) = (corner(0, 0, 0), corner(1, 1, 1));
+                if fr >= fg && fg >= fb {
+                    mix(&[
+                        (1.0 - fr, c000),
+                        (fr - fg, corner(1, 0, 0)),
+                        (fg - fb, corner(1, 1, 0)),
+                        (fb, c111),
+                    ])
+                } else if fr >= fb && fb >= fg {
+                    mix(&[
+                        (1.0 - fr, c000),
+                        (fr - fb, corner(1, 0, 0)),
+                        (fb - fg, corner(1, 0, 1)),
+                        (fg, c111),
+                    ])
+                } else if fb >= fr && fr >= fg {
+                    mix(&[
+                        (1.0 - fb, c000),
+                        (fb - fr, corner(0, 0, 1)),
+                        (fr - fg, corner(1, 0, 1)),
+                        (fg, c111),
+                    ])
+                } else if fg >= fr && fr >= fb {
+                    mix(&[
+                        (1.0 - fg, c000),
+                        (fg - fr, corner(0, 1, 0)),
+                        (fr - fb, corner(1, 1, 0)),
+                        (fb, c111),
+                    ])
+                } else if fg >= fb && fb >= fr {
+                    mix(&[
+                        (1.0 - fg, c000),
+                        (fg - fb, corner(0, 1, 0)),
+                        (fb - fr, corner(0, 1, 1)),
+                        (fr, c111),
+                    ])
+                } else {
+                    mix(&[
+                        (1.0 - fb, c000),
+                        (fb - fg, corner(0, 0, 1)),
+                        (fg - fr, corner(0, 1, 1)),
+                        (fr, c111),
+                    ])
+                }
+            }
+        }
+    }
+
+    /// Grade the image, `intensity` mixes the original (0) and the LUT colors (1)
+    pub fn apply(
+        &self,
+        img: DynamicImage,
+        interpolation: LutInterpolation,
+        intensity: f32,
+    ) -> DynamicImage {
+        let intensity = intensity.clamp(0.0, 1.0);
+        if intensity == 0.0 {
+            trace!("LUT intensity is 0");
+            return img;
+        }
+        let mut buffer = image_adjustments::to_working(&img, false);
+        image_adjustments::map_pixels(&mut buffer, |rgb| {
+            let graded = self.lookup(rgb, interpolation);
+            [0, 1, 2].map(|c| rgb[c] + (graded[c] - rgb[c]) * intensity)
+        });
+        trace!(
+            "LUT applied ({} entries per side, {:?}, intensity {})",
+            self.size,
+            interpolation,
+            intensity
+        );
+        image_adjustments::from_working(buffer, &img, false)
+    }
+}
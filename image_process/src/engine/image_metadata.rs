@@ -0,0 +1,457 @@
+use image::codecs::{
+    bmp::BmpDecoder, dds::DdsDecoder, farbfeld::FarbfeldDecoder, gif::GifDecoder, hdr::HdrAdapter,
+    ico::IcoDecoder, jpeg::JpegDecoder, png::PngDecoder, pnm::PnmDecoder, tga::TgaDecoder,
+    tiff::TiffDecoder,
+};
+use image::{ColorType, ImageDecoder, ImageFormat, ImageResult};
+use log::*;
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+
+use super::{image_orientation, ErrorCode, ErrorStage, ProcessingError};
+
+/// TIFF tag of the embedded ICC profile
+pub(super) const TIFF_TAG_ICC_PROFILE: u16 = 34675;
+
+/// Header information of an encoded image, read without decoding the pixels
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct ImageInfo {
+    format: String,
+    //As stored, before the EXIF orientation
+    width: u32,
+    height: u32,
+    color_type: String,
+    bit_depth: u8,
+    has_alpha: bool,
+    frame_count: u32,
+    has_icc_profile: bool,
+    orientation: u32,
+    exif: Option<ExifInfo>,
+}
+
+#[wasm_bindgen]
+impl ImageInfo {
+    /// Ex : `PNG`, `JPEG`, `WEBP`
+    pub fn get_format(&self) -> String {
+        self.format.clone()
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Ex : `Rgb8`, `Rgba16`, `L8`
+    pub fn get_color_type(&self) -> String {
+        self.color_type.clone()
+    }
+
+    /// Bits per channel
+    pub fn get_bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.has_alpha
+    }
+
+    /// More than 1 for animated GIF, PNG and WebP
+    pub fn get_frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    pub fn has_icc_profile(&self) -> bool {
+        self.has_icc_profile
+    }
+
+    /// EXIF orientation, from 1 (upright) to 8
+    pub fn get_orientation(&self) -> u32 {
+        self.orientation
+    }
+
+    pub fn get_exif(&self) -> Option<ExifInfo> {
+        self.exif.clone()
+    }
+}
+
+/// Commonly used EXIF fields, None when the field is missing
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct ExifInfo {
+    camera_make: Option<String>,
+    camera_model: Option<String>,
+    date_time: Option<String>,
+    exposure_time: Option<String>,
+    f_number: Option<f64>,
+    iso: Option<u32>,
+    focal_length: Option<f64>,
+    gps_latitude: Option<f64>,
+    gps_longitude: Option<f64>,
+    gps_altitude: Option<f64>,
+}
+
+#[wasm_bindgen]
+impl ExifInfo {
+    pub fn get_camera_make(&self) -> Option<String> {
+        self.camera_make.clone()
+    }
+
+    pub fn get_camera_model(&self) -> Option<String> {
+        self.camera_model.clone()
+    }
+
+    /// Date of the shot, ex : `2022-03-14 15:09:26`
+    pub fn get_date_time(&self) -> Option<String> {
+        self.date_time.clone()
+    }
+
+    /// Ex : `1/125`
+    pub fn get_exposure_time(&self) -> Option<String> {
+        self.exposure_time.clone()
+    }
+
+    pub fn get_f_number(&self) -> Option<f64> {
+        self.f_number
+    }
+
+    pub fn get_iso(&self) -> Option<u32> {
+        self.iso
+    }
+
+    /// In millimeters
+    pub fn get_focal_length(&self) -> Option<f64> {
+        self.focal_length
+    }
+
+    /// Decimal degrees, negative in the south
+    pub fn get_gps_latitude(&self) -> Option<f64> {
+        self.gps_latitude
+    }
+
+    /// Decimal degrees, negative in the west
+    pub fn get_gps_longitude(&self) -> Option<f64> {
+        self.gps_longitude
+    }
+
+    /// In meters, negative below the sea level
+    pub fn get_gps_altitude(&self) -> Option<f64> {
+        self.gps_altitude
+    }
+}
+
+impl ExifInfo {
+    fn read(exif: &exif::Exif) -> ExifInfo {
+        let field = |tag| exif.get_field(tag, exif::In::PRIMARY);
+        let text = |tag| match &field(tag)?.value {
+            exif::Value::Ascii(values) => values
+                .first()
+                .map(|v| String::from_utf8_lossy(v).trim().to_string())
+                .filter(|v| !v.is_empty()),
+            _ => None,
+        };
+        let rational = |tag| match &field(tag)?.value {
+            exif::Value::Rational(values) => values.first().map(|v| v.to_f64()),
+            _ => None,
+        };
+        let reference = |tag| text(tag).and_then(|v| v.chars().next());
+
+        let coordinate = |tag, ref_tag, negative| match &field(tag)?.value {
+            exif::Value::Rational(dms) if dms.len() >= 3 => {
+                let degrees = dms[0].to_f64() + dms[1].to_f64() / 60.0 + dms[2].to_f64() / 3600.0;
+                if reference(ref_tag) == Some(negative) {
+                    Some(-degrees)
+                } else {
+                    Some(degrees)
+                }
+            }
+            _ => None,
+        };
+
+        //Date with dashes, EXIF stores `2022:03:14 15:09:26`
+        let date_time = text(exif::Tag::DateTimeOriginal)
+            .or_else(|| text(exif::Tag::DateTime))
+            .map(|date| date.replacen(':', "-", 2));
+
+        let below_sea_level = matches!(
+            field(exif::Tag::GPSAltitudeRef).map(|f| &f.value),
+            Some(exif::Value::Byte(v)) if v.first() == Some(&1)
+        );
+
+        ExifInfo {
+            camera_make: text(exif::Tag::Make),
+            camera_model: text(exif::Tag::Model),
+            date_time,
+            exposure_time: field(exif::Tag::ExposureTime).map(|f| f.display_value().to_string()),
+            f_number: rational(exif::Tag::FNumber),
+            iso: field(exif::Tag::PhotographicSensitivity).and_then(|f| f.value.get_uint(0)),
+            focal_length: rational(exif::Tag::FocalLength),
+            gps_latitude: coordinate(exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef, 'S'),
+            gps_longitude: coordinate(exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef, 'W'),
+            gps_altitude: rational(exif::Tag::GPSAltitude).map(|altitude| {
+                if below_sea_level {
+                    -altitude
+                } else {
+                    altitude
+                }
+            }),
+        }
+    }
+}
+
+/// Dimensions and color type from the decoder, only the header is read
+fn decoder_header<'a, D: ImageDecoder<'a>>(
+    decoder: ImageResult<D>,
+) -> ImageResult<(u32, u32, ColorType)> {
+    let decoder = decoder?;
+    let (width, height) = decoder.dimensions();
+    Ok((width, height, decoder.color_type()))
+}
+
+fn read_header(format: ImageFormat, bytes: &[u8]) -> Result<(u32, u32, ColorType), ProcessingError> {
+    let cursor = Cursor::new(bytes);
+    let header = match format {
+        ImageFormat::Png => decoder_header(PngDecoder::new(cursor)),
+        ImageFormat::Jpeg => decoder_header(JpegDecoder::new(cursor)),
+        ImageFormat::Gif => decoder_header(GifDecoder::new(cursor)),
+        ImageFormat::Tiff => decoder_header(TiffDecoder::new(cursor)),
+        ImageFormat::Bmp => decoder_header(BmpDecoder::new(cursor)),
+        ImageFormat::Ico => decoder_header(IcoDecoder::new(cursor)),
+        ImageFormat::Pnm => decoder_header(PnmDecoder::new(cursor)),
+        ImageFormat::Tga => decoder_header(TgaDecoder::new(cursor)),
+        ImageFormat::Farbfeld => decoder_header(FarbfeldDecoder::new(cursor)),
+        ImageFormat::Dds => decoder_header(DdsDecoder::new(cursor)),
+        ImageFormat::Hdr => decoder_header(HdrAdapter::new(cursor)),
+        //The WebP decoder of the image crate decodes the whole frame
+        ImageFormat::WebP => {
+            return webp_header(bytes).ok_or_else(|| {
+                ProcessingError::from(ErrorCode::UnableToDecode)
+                    .with_cause("invalid WebP header")
+                    .with_detail("format", "WebP")
+            })
+        }
+        _ => {
+            error!("No header reader for {:?}", format);
+            return Err(ProcessingError::new(ErrorCode::NotImplemented, ErrorStage::DECODE)
+                .with_detail("format", format!("{:?}", format)));
+        }
+    };
+    header.map_err(|e| {
+        error!("Unable to read the {:?} header : {}", format, e);
+        ProcessingError::from(ErrorCode::UnableToDecode)
+            .with_cause(e)
+            .with_detail("format", format!("{:?}", format))
+    })
+}
+
+/// Format, dimensions and color type of the encoded image
+pub fn read_format_header(bytes: &[u8]) -> Result<(ImageFormat, u32, u32, ColorType), ProcessingError> {
+    if bytes.is_empty() {
+        return Err(ErrorCode::ImageEmpty.into());
+    }
+    let format = image::guess_format(bytes).map_err(|e| {
+        error!("Unknown image format : {}", e);
+        ProcessingError::from(ErrorCode::UnableToDecode).with_cause(e)
+    })?;
+    let (width, height, color_type) = read_header(format, bytes)?;
+    Ok((format, width, height, color_type))
+}
+
+/// Read the format, dimensions, color type, frames, ICC and EXIF of the encoded image
+pub fn read_info(bytes: &[u8]) -> Result<ImageInfo, ProcessingError> {
+    let (format, width, height, color_type) = read_format_header(bytes)?;
+
+    let exif = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(bytes))
+        .ok();
+    let has_icc_profile = match format {
+        ImageFormat::Png => png_chunks(bytes).any(|(kind, _)| kind == b"iCCP"),
+        ImageFormat::Jpeg => jpeg_segments(bytes)
+            .any(|(marker, data)| marker == 0xE2 && data.starts_with(b"ICC_PROFILE\0")),
+        ImageFormat::WebP => riff_chunks(bytes).any(|(kind, _)| kind == b"ICCP"),
+        ImageFormat::Tiff => exif.as_ref().is_some_and(|exif| {
+            exif.get_field(
+                exif::Tag(exif::Context::Tiff, TIFF_TAG_ICC_PROFILE),
+                exif::In::PRIMARY,
+            )
+            .is_some()
+        }),
+        _ => false,
+    };
+    Ok(ImageInfo {
+        format: format!("{:?}", format).to_uppercase(),
+        width,
+        height,
+        color_type: format!("{:?}", color_type),
+        bit_depth: (color_type.bits_per_pixel() / color_type.channel_count() as u16) as u8,
+        has_alpha: color_type.has_alpha(),
+        frame_count: frame_count(format, bytes),
+        has_icc_profile,
+        orientation: image_orientation::read_orientation(bytes),
+        exif: exif.as_ref().map(ExifInfo::read),
+    })
+}
+
+/// Number of frames of an animated GIF, APNG or WebP, 1 for a still image
+pub fn frame_count(format: ImageFormat, bytes: &[u8]) -> u32 {
+    match format {
+        ImageFormat::Gif => gif_frame_count(bytes),
+        ImageFormat::Png => png_chunks(bytes)
+            .find(|(kind, _)| kind == b"acTL")
+            .and_then(|(_, data)| data.get(..4))
+            .map_or(1, |n| u32::from_be_bytes([n[0], n[1], n[2], n[3]])),
+        ImageFormat::WebP => riff_chunks(bytes)
+            .filter(|(kind, _)| kind == b"ANMF")
+            .count()
+            .max(1) as u32,
+        _ => 1,
+    }
+}
+
+fn u32_le(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+pub(super) fn u24_le(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 3)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]))
+}
+
+/// Chunks of a PNG file : (type, data)
+pub(super) fn png_chunks(bytes: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut at = 8;
+    std::iter::from_fn(move || {
+        let length = bytes.get(at..at + 4)?;
+        let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+        let kind = bytes.get(at + 4..at + 8)?;
+        //The length comes from the file, it can overflow usize on wasm32
+        let end = at.checked_add(8)?.checked_add(length)?;
+        let data = bytes.get(at + 8..end)?;
+        //Data then CRC
+        at = end.checked_add(4)?;
+        Some((kind, data))
+    })
+}
+
+/// Chunks of a RIFF (WebP) file : (fourcc, data)
+pub(super) fn riff_chunks(bytes: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    riff_chunks_from(bytes, 12)
+}
+
+/// RIFF chunks starting at `at`, also used for the sub-chunks of a WebP frame
+pub(super) fn riff_chunks_from(bytes: &[u8], mut at: usize) -> impl Iterator<Item = (&[u8], &[u8])> {
+    std::iter::from_fn(move || {
+        let kind = bytes.get(at..at.checked_add(4)?)?;
+        let length = u32_le(bytes, at + 4)? as usize;
+        //The length comes from the file, it can overflow usize on wasm32
+        let end = at.checked_add(8)?.checked_add(length)?;
+        let data = bytes.get(at + 8..end.min(bytes.len()))?;
+        //Chunks are padded to an even size
+        at = end.checked_add(length & 1)?;
+        Some((kind, data))
+    })
+}
+
+/// Segments of a JPEG file before the image data : (marker, data)
+pub(super) fn jpeg_segments(bytes: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut at = 2;
+    std::iter::from_fn(move || {
+        if *bytes.get(at)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(at + 1)?;
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+        let length = bytes.get(at + 2..at + 4)?;
+        let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+        let data = bytes.get(at + 4..at + 2 + length.max(2))?;
+        at += 2 + length;
+        Some((marker, data))
+    })
+}
+
+/// Canvas size and alpha of a WebP file from its VP8X, VP8L or VP8 header
+fn webp_header(bytes: &[u8]) -> Option<(u32, u32, ColorType)> {
+    let (kind, data) = riff_chunks(bytes).next()?;
+    webp_chunk_header(kind, data)
+}
+
+/// Size and alpha from a VP8X, VP8L or VP8 chunk, without decoding it
+pub(super) fn webp_chunk_header(kind: &[u8], data: &[u8]) -> Option<(u32, u32, ColorType)> {
+    let with_alpha = |alpha| {
+        if alpha {
+            ColorType::Rgba8
+        } else {
+            ColorType::Rgb8
+        }
+    };
+    match kind {
+        b"VP8X" => {
+            let alpha = data.first()? & 0x10 != 0;
+            Some((
+                u24_le(data, 4)? + 1,
+                u24_le(data, 7)? + 1,
+                with_alpha(alpha),
+            ))
+        }
+        b"VP8L" => {
+            let bits = u32_le(data, 1)?;
+            Some((
+                (bits & 0x3FFF) + 1,
+                ((bits >> 14) & 0x3FFF) + 1,
+                with_alpha((bits >> 28) & 1 != 0),
+            ))
+        }
+        b"VP8 " => {
+            let size = data.get(6..10)?;
+            Some((
+                u16::from_le_bytes([size[0], size[1]]) as u32 & 0x3FFF,
+                u16::from_le_bytes([size[2], size[3]]) as u32 & 0x3FFF,
+                ColorType::Rgb8,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Count the image descriptors of a GIF file, skipping the data sub-blocks
+fn gif_frame_count(bytes: &[u8]) -> u32 {
+    let skip_sub_blocks = |mut at: usize| {
+        while let Some(&size) = bytes.get(at) {
+            at += 1 + size as usize;
+            if size == 0 {
+                break;
+            }
+        }
+        at
+    };
+    let color_table = |flags: u8| {
+        if flags & 0x80 != 0 {
+            3 << ((flags & 0x07) + 1)
+        } else {
+            0
+        }
+    };
+
+    let mut at = 13 + bytes.get(10).map_or(0, |&flags| color_table(flags));
+    let mut frames = 0;
+    while let Some(&block) = bytes.get(at) {
+        match block {
+            //Extension : label then sub-blocks
+            0x21 => at = skip_sub_blocks(at + 2),
+            //Image descriptor, local color table, LZW code size then sub-blocks
+            0x2C => {
+                frames += 1;
+                let flags = bytes.get(at + 9).copied().unwrap_or(0);
+                at = skip_sub_blocks(at + 10 + color_table(flags) + 1);
+            }
+            _ => break,
+        }
+    }
+    frames.max(1)
+}
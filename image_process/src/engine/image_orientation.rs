@@ -0,0 +1,60 @@
+use image::DynamicImage;
+use log::*;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Value of the EXIF Orientation tag when the image is stored upright
+pub const ORIENTATION_NORMAL: u32 = 1;
+
+//Default of every decoded image, overridden by the per-call options
+static AUTO_ORIENT: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable the EXIF orientation correction for the next decoded images
+pub fn set_auto_orient(enabled: bool) {
+    AUTO_ORIENT.store(enabled, Ordering::Relaxed);
+}
+
+pub fn auto_orient() -> bool {
+    AUTO_ORIENT.load(Ordering::Relaxed)
+}
+
+/// EXIF Orientation tag of the encoded image (JPEG, TIFF, PNG, WebP), from 1 to 8
+/// `ORIENTATION_NORMAL` when the tag is missing or invalid
+pub fn read_orientation(bytes: &[u8]) -> u32 {
+    let exif = match exif::Reader::new().read_from_container(&mut Cursor::new(bytes)) {
+        Ok(exif) => exif,
+        Err(e) => {
+            trace!("No EXIF data : {}", e);
+            return ORIENTATION_NORMAL;
+        }
+    };
+
+    match exif
+        .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+    {
+        Some(orientation @ 1..=8) => orientation,
+        Some(orientation) => {
+            warn!("Invalid EXIF orientation {}, ignored", orientation);
+            ORIENTATION_NORMAL
+        }
+        None => ORIENTATION_NORMAL,
+    }
+}
+
+/// Rotate / flip the image so it is displayed upright
+pub fn apply_orientation(img: DynamicImage, orientation: u32) -> DynamicImage {
+    if orientation != ORIENTATION_NORMAL {
+        trace!("Apply EXIF orientation {}", orientation);
+    }
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
@@ -0,0 +1,167 @@
+use image::{ColorType, DynamicImage, ImageOutputFormat};
+use log::*;
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+
+use super::{Animation, EmbeddedMetadata, ErrorCode, MetadataOptions, ProcessingError};
+
+/// Encoding used to write the processed image
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    PNG,
+    JPEG,
+    //Lossless, the image crate has no lossy WebP encoder without libwebp
+    WEBP,
+    BMP,
+    TIFF,
+    GIF,
+    FARBFELD,
+}
+
+impl OutputFormat {
+    /// The encoder takes a quality parameter
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, OutputFormat::JPEG)
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            OutputFormat::PNG => "image/png",
+            OutputFormat::JPEG => "image/jpeg",
+            OutputFormat::WEBP => "image/webp",
+            OutputFormat::BMP => "image/bmp",
+            OutputFormat::TIFF => "image/tiff",
+            OutputFormat::GIF => "image/gif",
+            OutputFormat::FARBFELD => "image/x-farbfeld",
+        }
+    }
+
+    /// Keeps all the frames of an animated input (PNG as APNG)
+    pub fn supports_animation(&self) -> bool {
+        matches!(self, OutputFormat::GIF | OutputFormat::PNG)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::PNG => "png",
+            OutputFormat::JPEG => "jpg",
+            OutputFormat::WEBP => "webp",
+            OutputFormat::BMP => "bmp",
+            OutputFormat::TIFF => "tiff",
+            OutputFormat::GIF => "gif",
+            OutputFormat::FARBFELD => "ff",
+        }
+    }
+}
+
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone)]
+pub struct OutputOptions {
+    pub format: OutputFormat,
+    //Only used by lossy formats (JPEG), from 1 to 100
+    pub quality: u8,
+    //EXIF / ICC / XMP copied from the input, stripped by default
+    pub metadata: MetadataOptions,
+    //Encode every frame of an animated input when the format supports it, true by default
+    pub keep_animation: bool,
+}
+
+#[wasm_bindgen]
+impl OutputOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(format: OutputFormat, quality: Option<u8>) -> OutputOptions {
+        OutputOptions {
+            format,
+            quality: quality.unwrap_or(OutputOptions::DEFAULT_QUALITY),
+            metadata: MetadataOptions::default(),
+            keep_animation: true,
+        }
+    }
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::PNG,
+            quality: OutputOptions::DEFAULT_QUALITY,
+            metadata: MetadataOptions::default(),
+            keep_animation: true,
+        }
+    }
+}
+
+impl OutputOptions {
+    const DEFAULT_QUALITY: u8 = 85;
+
+    /// Match the options with the encoder of the image crate
+    fn image_output_format(&self) -> ImageOutputFormat {
+        match self.format {
+            OutputFormat::PNG => ImageOutputFormat::Png,
+            OutputFormat::JPEG => ImageOutputFormat::Jpeg(self.quality.clamp(1, 100)),
+            OutputFormat::WEBP => ImageOutputFormat::WebP,
+            OutputFormat::BMP => ImageOutputFormat::Bmp,
+            OutputFormat::TIFF => ImageOutputFormat::Tiff,
+            OutputFormat::GIF => ImageOutputFormat::Gif,
+            OutputFormat::FARBFELD => ImageOutputFormat::Farbfeld,
+        }
+    }
+
+    /// Convert Dynamic image to bytes with the selected encoding
+    pub fn encode(&self, img: &DynamicImage) -> Result<Vec<u8>, ProcessingError> {
+        let format = self.image_output_format();
+        trace!("Convert image to bytes ({:?})", self.format);
+
+        //Some encoders only accept a specific color type
+        let converted = match (self.format, img.color()) {
+            (OutputFormat::PNG, _) => None,
+            (OutputFormat::JPEG, _) => Some(DynamicImage::ImageRgb8(img.to_rgb8())),
+            (OutputFormat::FARBFELD, _) => Some(DynamicImage::ImageRgba16(img.to_rgba16())),
+            (_, ColorType::Rgb8 | ColorType::Rgba8) => None,
+            _ => Some(DynamicImage::ImageRgba8(img.to_rgba8())),
+        };
+
+        let mut edited_image_bytes = Vec::new();
+        match converted
+            .as_ref()
+            .unwrap_or(img)
+            .write_to(&mut Cursor::new(&mut edited_image_bytes), format)
+        {
+            Ok(_) => Ok(edited_image_bytes),
+            Err(e) => {
+                error!("Unable to encode image as {:?} : {}", self.format, e);
+                Err(ProcessingError::from(ErrorCode::UnableToSave)
+                    .with_cause(e)
+                    .with_detail("format", format!("{:?}", self.format)))
+            }
+        }
+    }
+
+    /// Encode and copy the metadata of the input allowed by `self.metadata`
+    pub fn encode_with_metadata(
+        &self,
+        img: &DynamicImage,
+        metadata: &EmbeddedMetadata,
+    ) -> Result<Vec<u8>, ProcessingError> {
+        metadata.embed(self.encode(img)?, self.format, &self.metadata)
+    }
+
+    /// Encode every frame as an animated GIF or APNG, a still image for the other formats
+    pub fn encode_animation(
+        &self,
+        animation: &Animation,
+        metadata: &EmbeddedMetadata,
+    ) -> Result<Vec<u8>, ProcessingError> {
+        let bytes = match self.format {
+            OutputFormat::GIF => animation.encode_gif()?,
+            OutputFormat::PNG => animation.encode_apng()?,
+            _ => return self.encode_with_metadata(&animation.frames()[0].image, metadata),
+        };
+        if self.metadata.is_strip_all() {
+            Ok(bytes)
+        } else {
+            metadata.embed(bytes, self.format, &self.metadata)
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use super::{image_filters::{self, ColorRgba}, ErrorCode, ProcessingError};
+use image::Rgba;
+use log::*;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+/// Names of the palettes available with `ColorPalette::preset`
+pub const PALETTE_PRESETS: [&str; 6] = ["default", "sunset", "ocean", "forest", "pastel", "rainbow"];
+
+/// Ordered list of colors, used by the band filter
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct ColorPalette {
+    colors: Vec<ColorRgba>,
+}
+
+#[wasm_bindgen]
+impl ColorPalette {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ColorPalette {
+        ColorPalette::default()
+    }
+
+    /// Palette from its name, see `preset_names`
+    pub fn preset(name: &str) -> Result<ColorPalette, JsValue> {
+        Ok(ColorPalette::from_preset(name)?)
+    }
+
+    pub fn preset_names() -> Vec<String> {
+        PALETTE_PRESETS.iter().map(|name| name.to_string()).collect()
+    }
+
+    pub fn add(&mut self, color: ColorRgba) {
+        self.colors.push(color);
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+}
+
+impl ColorPalette {
+    pub fn from_colors(colors: Vec<ColorRgba>) -> ColorPalette {
+        ColorPalette { colors }
+    }
+
+    pub fn from_preset(name: &str) -> Result<ColorPalette, ProcessingError> {
+        let alpha = 150;
+        let colors = match name {
+            "default" => image_filters::default_band_colors(),
+            "sunset" => vec![
+                ColorRgba::new(255, 94, 58, alpha),
+                ColorRgba::new(255, 149, 0, alpha),
+                ColorRgba::new(255, 204, 0, alpha),
+                ColorRgba::new(255, 45, 85, alpha),
+                ColorRgba::new(88, 86, 214, alpha),
+            ],
+            "ocean" => vec![
+                ColorRgba::new(0, 63, 92, alpha),
+                ColorRgba::new(0, 119, 182, alpha),
+                ColorRgba::new(0, 180, 216, alpha),
+                ColorRgba::new(144, 224, 239, alpha),
+            ],
+            "forest" => vec![
+                ColorRgba::new(27, 67, 50, alpha),
+                ColorRgba::new(45, 106, 79, alpha),
+                ColorRgba::new(64, 145, 108, alpha),
+                ColorRgba::new(149, 213, 178, alpha),
+            ],
+            "pastel" => vec![
+                ColorRgba::new(255, 179, 186, alpha),
+                ColorRgba::new(255, 223, 186, alpha),
+                ColorRgba::new(255, 255, 186, alpha),
+                ColorRgba::new(186, 255, 201, alpha),
+                ColorRgba::new(186, 225, 255, alpha),
+            ],
+            "rainbow" => vec![
+                ColorRgba::new(255, 0, 0, alpha),
+                ColorRgba::new(255, 127, 0, alpha),
+                ColorRgba::new(255, 255, 0, alpha),
+                ColorRgba::new(0, 255, 0, alpha),
+                ColorRgba::new(0, 0, 255, alpha),
+                ColorRgba::new(75, 0, 130, alpha),
+                ColorRgba::new(148, 0, 211, alpha),
+            ],
+            _ => {
+                error!("Unknown palette preset : {}", name);
+                return Err(ProcessingError::from(ErrorCode::UnknownPreset).with_detail("name", name));
+            }
+        };
+        Ok(ColorPalette { colors })
+    }
+
+    pub fn colors(&self) -> &[ColorRgba] {
+        &self.colors
+    }
+
+    pub fn to_rgba(&self) -> Vec<Rgba<u8>> {
+        self.colors.iter().map(|c| Rgba::<u8>::from(*c)).collect()
+    }
+}
@@ -0,0 +1,502 @@
+use super::{
+    image_filters::{self, ColorRgba, FilterPixelType, GradientDirection},
+    image_geometry, image_presets, BlendMode, ColorPalette, Curves, ErrorCode, ProcessingError, Gradient, ImageParameters, Interpolation,
+    Levels, Lut3D, LutInterpolation, ResizeOptions,
+};
+use image::{DynamicImage, Rgba};
+use log::*;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+/// A single step of a pipeline
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    Parameters(ImageParameters),
+    Curves(Curves),
+    Levels(Levels),
+    Lut {
+        lut: Lut3D,
+        #[serde(default)]
+        interpolation: LutInterpolation,
+        #[serde(default = "Operation::default_intensity")]
+        intensity: f32,
+    },
+    Sobel,
+    Vignette {
+        strength: f32,
+        #[serde(default = "Operation::default_vignette_radius")]
+        radius: f32,
+    },
+    Grain {
+        amount: f32,
+        #[serde(default)]
+        seed: u32,
+    },
+    //Named preset, see `image_presets::PRESET_NAMES`
+    Preset {
+        name: String,
+        #[serde(default = "Operation::default_intensity")]
+        intensity: f32,
+    },
+    BandColor {
+        #[serde(default = "image_filters::default_band_colors")]
+        colors: Vec<ColorRgba>,
+        direction: GradientDirection,
+        #[serde(default)]
+        softness: f32,
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    Gradient {
+        from: ColorRgba,
+        to: ColorRgba,
+        direction: GradientDirection,
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    CustomGradient(Gradient),
+    Pixel {
+        pixel_type: FilterPixelType,
+        color: ColorRgba,
+        #[serde(default = "Operation::default_pixel_spacing")]
+        spacing: u32,
+        #[serde(default = "Operation::default_pixel_thickness")]
+        thickness: u32,
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    Resize {
+        width: u32,
+        height: u32,
+        #[serde(flatten)]
+        options: ResizeOptions,
+    },
+    Crop {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Rotate {
+        angle: f32,
+        #[serde(default)]
+        interpolation: Interpolation,
+        #[serde(default = "Operation::default_fill")]
+        fill: ColorRgba,
+        #[serde(default)]
+        expand: bool,
+    },
+}
+
+impl Operation {
+    /// Band color operation, with the default palette if none is given
+    pub fn band_color(
+        palette: Option<ColorPalette>,
+        direction: GradientDirection,
+        softness: Option<f32>,
+        blend_mode: Option<BlendMode>,
+    ) -> Operation {
+        Operation::BandColor {
+            colors: palette
+                .map(|palette| palette.colors().to_vec())
+                .unwrap_or_else(image_filters::default_band_colors),
+            direction,
+            softness: softness.unwrap_or(0.0),
+            blend_mode: blend_mode.unwrap_or_default(),
+        }
+    }
+
+    /// Pixel operation, with the default spacing and thickness if none are given
+    pub fn pixel(
+        pixel_type: FilterPixelType,
+        color: ColorRgba,
+        spacing: Option<u32>,
+        thickness: Option<u32>,
+        blend_mode: Option<BlendMode>,
+    ) -> Operation {
+        Operation::Pixel {
+            pixel_type,
+            color,
+            spacing: spacing.unwrap_or(image_filters::DEFAULT_PIXEL_SPACING),
+            thickness: thickness.unwrap_or(image_filters::DEFAULT_PIXEL_THICKNESS),
+            blend_mode: blend_mode.unwrap_or_default(),
+        }
+    }
+
+    /// LUT operation, tetrahedral interpolation and full intensity if none are given
+    pub fn lut(
+        lut: &Lut3D,
+        interpolation: Option<LutInterpolation>,
+        intensity: Option<f32>,
+    ) -> Operation {
+        Operation::Lut {
+            lut: lut.clone(),
+            interpolation: interpolation.unwrap_or_default(),
+            intensity: intensity.unwrap_or_else(Operation::default_intensity),
+        }
+    }
+
+    /// Preset operation, at full intensity if none is given
+    pub fn preset(name: &str, intensity: Option<f32>) -> Operation {
+        Operation::Preset {
+            name: name.to_string(),
+            intensity: intensity.unwrap_or_else(Operation::default_intensity),
+        }
+    }
+
+    fn default_intensity() -> f32 {
+        1.0
+    }
+
+    fn default_vignette_radius() -> f32 {
+        image_filters::DEFAULT_VIGNETTE_RADIUS
+    }
+
+    fn default_pixel_spacing() -> u32 {
+        image_filters::DEFAULT_PIXEL_SPACING
+    }
+
+    fn default_pixel_thickness() -> u32 {
+        image_filters::DEFAULT_PIXEL_THICKNESS
+    }
+
+    fn default_fill() -> ColorRgba {
+        ColorRgba::new(0, 0, 0, 0)
+    }
+
+    /// Free rotation, transparent fill and bilinear interpolation if none are given
+    pub fn rotate(
+        angle: f32,
+        interpolation: Option<Interpolation>,
+        fill: Option<ColorRgba>,
+        expand: Option<bool>,
+    ) -> Operation {
+        Operation::Rotate {
+            angle,
+            interpolation: interpolation.unwrap_or_default(),
+            fill: fill.unwrap_or_else(Operation::default_fill),
+            expand: expand.unwrap_or(false),
+        }
+    }
+
+    /// Name of the operation, as used in the JSON description
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Parameters(_) => "parameters",
+            Self::Curves(_) => "curves",
+            Self::Levels(_) => "levels",
+            Self::Lut { .. } => "lut",
+            Self::Sobel => "sobel",
+            Self::Vignette { .. } => "vignette",
+            Self::Grain { .. } => "grain",
+            Self::Preset { .. } => "preset",
+            Self::BandColor { .. } => "band_color",
+            Self::Gradient { .. } => "gradient",
+            Self::CustomGradient(_) => "custom_gradient",
+            Self::Pixel { .. } => "pixel",
+            Self::Resize { .. } => "resize",
+            Self::Crop { .. } => "crop",
+            Self::Rotate90 => "rotate90",
+            Self::Rotate180 => "rotate180",
+            Self::Rotate270 => "rotate270",
+            Self::FlipHorizontal => "flip_horizontal",
+            Self::FlipVertical => "flip_vertical",
+            Self::Rotate { .. } => "rotate",
+        }
+    }
+
+    /// Apply the operation on the decoded image
+    pub fn apply(&self, mut img: DynamicImage) -> Result<DynamicImage, ProcessingError> {
+        trace!("Apply pipeline operation : {:?}", self);
+        match self {
+            Self::Parameters(params) => Ok(params.apply_filter(img)),
+            Self::Curves(curves) => Ok(curves.apply(img)),
+            Self::Levels(levels) => Ok(levels.apply(img)),
+            Self::Lut {
+                lut,
+                interpolation,
+                intensity,
+            } => Ok(lut.apply(img, *interpolation, *intensity)),
+            Self::Sobel => image_filters::filter_sobel(img),
+            Self::Vignette { strength, radius } => {
+                image_filters::filter_vignette(img, *strength, *radius)
+            }
+            Self::Grain { amount, seed } => image_filters::filter_grain(img, *amount, *seed),
+            Self::Preset { name, intensity } => {
+                image_presets::apply_preset(img, name, *intensity)
+            }
+            Self::BandColor {
+                colors,
+                direction,
+                softness,
+                blend_mode,
+            } => image_filters::filter_band_color(
+                &mut img,
+                colors.iter().map(|c| Rgba::<u8>::from(*c)).collect(),
+                *direction,
+                *softness,
+                *blend_mode,
+            ),
+            Self::Gradient {
+                from,
+                to,
+                direction,
+                blend_mode,
+            } => image_filters::filter_gradient(
+                &mut img,
+                (*from).into(),
+                (*to).into(),
+                *direction,
+                *blend_mode,
+            ),
+            Self::CustomGradient(gradient) => {
+                image_filters::filter_custom_gradient(&mut img, gradient)
+            }
+            Self::Pixel {
+                pixel_type,
+                color,
+                spacing,
+                thickness,
+                blend_mode,
+            } => image_filters::filter_pixel(
+                &mut img,
+                *pixel_type,
+                *color,
+                *spacing,
+                *thickness,
+                *blend_mode,
+            ),
+            Self::Resize {
+                width,
+                height,
+                options,
+            } => Ok(options.apply(&img, *width, *height)),
+            Self::Crop {
+                x,
+                y,
+                width,
+                height,
+            } => image_geometry::crop(&img, *x, *y, *width, *height),
+            Self::Rotate90 => Ok(img.rotate90()),
+            Self::Rotate180 => Ok(img.rotate180()),
+            Self::Rotate270 => Ok(img.rotate270()),
+            Self::FlipHorizontal => Ok(img.fliph()),
+            Self::FlipVertical => Ok(img.flipv()),
+            Self::Rotate {
+                angle,
+                interpolation,
+                fill,
+                expand,
+            } => Ok(image_geometry::rotate(&img, *angle, *interpolation, *fill, *expand)),
+        }
+    }
+}
+
+/// Ordered list of operations applied on a single decoded image
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct Pipeline {
+    operations: Vec<Operation>,
+}
+
+#[wasm_bindgen]
+impl Pipeline {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Build a pipeline from a JSON array of operations, ex :
+    /// `[{"op": "resize", "width": 800, "height": 600, "mode": "FILL"}, {"op": "sobel"}]`
+    pub fn from_json(json: &str) -> Result<Pipeline, JsValue> {
+        Ok(Pipeline {
+            operations: Pipeline::parse_operations(json)?,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn add_parameters(&mut self, params: ImageParameters) {
+        self.operations.push(Operation::Parameters(params));
+    }
+
+    pub fn add_curves(&mut self, curves: &Curves) {
+        self.operations.push(Operation::Curves(curves.clone()));
+    }
+
+    pub fn add_levels(&mut self, levels: Levels) {
+        self.operations.push(Operation::Levels(levels));
+    }
+
+    /// Intensity from 0 (original colors) to 1 (LUT colors, the default)
+    pub fn add_lut(
+        &mut self,
+        lut: &Lut3D,
+        interpolation: Option<LutInterpolation>,
+        intensity: Option<f32>,
+    ) {
+        self.operations
+            .push(Operation::lut(lut, interpolation, intensity));
+    }
+
+    pub fn add_sobel(&mut self) {
+        self.operations.push(Operation::Sobel);
+    }
+
+    /// `strength` from 0 to 1, the default `radius` keeps the centre untouched
+    pub fn add_vignette(&mut self, strength: f32, radius: Option<f32>) {
+        self.operations.push(Operation::Vignette {
+            strength,
+            radius: radius.unwrap_or(image_filters::DEFAULT_VIGNETTE_RADIUS),
+        });
+    }
+
+    pub fn add_grain(&mut self, amount: f32, seed: Option<u32>) {
+        self.operations.push(Operation::Grain {
+            amount,
+            seed: seed.unwrap_or(0),
+        });
+    }
+
+    /// Named preset, see `list_presets`
+    pub fn add_preset(&mut self, name: &str, intensity: Option<f32>) {
+        self.operations.push(Operation::preset(name, intensity));
+    }
+
+    /// Without palette the default one is used, `softness` blends the bands edges (0 to 1)
+    pub fn add_band_color(
+        &mut self,
+        direction: GradientDirection,
+        palette: Option<ColorPalette>,
+        softness: Option<f32>,
+        blend_mode: Option<BlendMode>,
+    ) {
+        self.operations.push(Operation::band_color(
+            palette, direction, softness, blend_mode,
+        ));
+    }
+
+    pub fn add_gradient(
+        &mut self,
+        from: ColorRgba,
+        to: ColorRgba,
+        direction: GradientDirection,
+        blend_mode: Option<BlendMode>,
+    ) {
+        self.operations.push(Operation::Gradient {
+            from,
+            to,
+            direction,
+            blend_mode: blend_mode.unwrap_or_default(),
+        });
+    }
+
+    pub fn add_custom_gradient(&mut self, gradient: &Gradient) {
+        self.operations
+            .push(Operation::CustomGradient(gradient.clone()));
+    }
+
+    pub fn add_pixel(
+        &mut self,
+        pixel_type: FilterPixelType,
+        color: ColorRgba,
+        spacing: Option<u32>,
+        thickness: Option<u32>,
+        blend_mode: Option<BlendMode>,
+    ) {
+        self.operations.push(Operation::pixel(
+            pixel_type, color, spacing, thickness, blend_mode,
+        ));
+    }
+
+    /// Fit in the box with the Lanczos3 filter without options
+    pub fn add_resize(&mut self, width: u32, height: u32, options: Option<ResizeOptions>) {
+        self.operations.push(Operation::Resize {
+            width,
+            height,
+            options: options.unwrap_or_default(),
+        });
+    }
+
+    pub fn add_crop(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        self.operations.push(Operation::Crop {
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    pub fn add_rotate90(&mut self) {
+        self.operations.push(Operation::Rotate90);
+    }
+
+    pub fn add_rotate180(&mut self) {
+        self.operations.push(Operation::Rotate180);
+    }
+
+    pub fn add_rotate270(&mut self) {
+        self.operations.push(Operation::Rotate270);
+    }
+
+    pub fn add_flip_horizontal(&mut self) {
+        self.operations.push(Operation::FlipHorizontal);
+    }
+
+    pub fn add_flip_vertical(&mut self) {
+        self.operations.push(Operation::FlipVertical);
+    }
+
+    /// Rotate by `angle` degrees clockwise, `expand` grows the canvas to keep the corners
+    pub fn add_rotate(
+        &mut self,
+        angle: f32,
+        interpolation: Option<Interpolation>,
+        fill: Option<ColorRgba>,
+        expand: Option<bool>,
+    ) {
+        self.operations
+            .push(Operation::rotate(angle, interpolation, fill, expand));
+    }
+}
+
+impl Pipeline {
+    pub fn parse_operations(json: &str) -> Result<Vec<Operation>, ProcessingError> {
+        serde_json::from_str(json).map_err(|e| {
+            error!("Unable to parse pipeline description : {}", e);
+            ProcessingError::from(ErrorCode::InvalidParsing)
+                .with_cause(&e)
+                .with_detail("line", e.line())
+                .with_detail("column", e.column())
+        })
+    }
+
+    pub fn push(&mut self, operation: Operation) {
+        self.operations.push(operation);
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Run every operation in order on the same image
+    pub fn apply(&self, img: DynamicImage) -> Result<DynamicImage, ProcessingError> {
+        info!("Run pipeline of {} operations", self.operations.len());
+        self.operations
+            .iter()
+            .try_fold(img, |img, operation| operation.apply(img))
+    }
+}
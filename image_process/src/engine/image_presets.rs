@@ -0,0 +1,159 @@
+use image::DynamicImage;
+use log::*;
+
+use super::{
+    image_adjustments::{self, Adjustment},
+    image_filters::{ColorRgba, GradientDirection},
+    BlendMode, ErrorCode, ImageParameters, Levels, Operation, ProcessingError,
+};
+
+/// Names accepted by `apply_preset`
+pub const PRESET_NAMES: [&str; 8] = [
+    "vintage", "noir", "warm", "cool", "fade", "sepia", "lomo", "vivid",
+];
+
+//Same seed for every preset so a preview and its export get the same grain
+const GRAIN_SEED: u32 = 7;
+
+/// Operations of a preset, built from the other filters
+pub fn preset_operations(name: &str) -> Result<Vec<Operation>, ProcessingError> {
+    let params = |adjust: fn(&mut ImageParameters)| {
+        let mut params = ImageParameters::default();
+        adjust(&mut params);
+        Operation::Parameters(params)
+    };
+    let operations = match name {
+        "vintage" => vec![
+            params(|p| {
+                p.constrast = Some(-10.0);
+                p.saturation = Some(-25.0);
+                p.temperature = Some(25.0);
+                p.highlights = Some(-15.0);
+            }),
+            Operation::Gradient {
+                from: ColorRgba::new(255, 214, 160, 60),
+                to: ColorRgba::new(110, 80, 150, 60),
+                direction: GradientDirection::VERTICAL,
+                blend_mode: BlendMode::SOFT_LIGHT,
+            },
+            Operation::Vignette {
+                strength: 0.35,
+                radius: 0.4,
+            },
+            Operation::Grain {
+                amount: 0.25,
+                seed: GRAIN_SEED,
+            },
+        ],
+        "noir" => vec![
+            params(|p| {
+                p.grayscale = Some(true);
+                p.constrast = Some(35.0);
+                p.shadows = Some(-20.0);
+            }),
+            Operation::Vignette {
+                strength: 0.45,
+                radius: 0.35,
+            },
+            Operation::Grain {
+                amount: 0.3,
+                seed: GRAIN_SEED,
+            },
+        ],
+        "warm" => vec![params(|p| {
+            p.temperature = Some(35.0);
+            p.tint = Some(5.0);
+            p.vibrance = Some(15.0);
+        })],
+        "cool" => vec![
+            params(|p| {
+                p.temperature = Some(-35.0);
+                p.tint = Some(-5.0);
+                p.saturation = Some(-5.0);
+            }),
+            Operation::Gradient {
+                from: ColorRgba::new(120, 170, 255, 40),
+                to: ColorRgba::new(120, 170, 255, 0),
+                direction: GradientDirection::VERTICAL,
+                blend_mode: BlendMode::SCREEN,
+            },
+        ],
+        "fade" => vec![
+            params(|p| {
+                p.constrast = Some(-20.0);
+                p.saturation = Some(-20.0);
+            }),
+            //Lifted blacks and dimmed whites
+            Operation::Levels(Levels::new(None, None, None, Some(35.0), Some(235.0))),
+        ],
+        "sepia" => vec![
+            params(|p| {
+                p.grayscale = Some(true);
+                p.temperature = Some(60.0);
+                //Tone the gray image
+                p.order = Some(vec![Adjustment::GRAYSCALE, Adjustment::WHITE_BALANCE]);
+            }),
+            Operation::BandColor {
+                colors: vec![ColorRgba::new(112, 66, 20, 90)],
+                direction: GradientDirection::VERTICAL,
+                softness: 0.0,
+                blend_mode: BlendMode::COLOR,
+            },
+            Operation::Vignette {
+                strength: 0.2,
+                radius: 0.5,
+            },
+        ],
+        "lomo" => vec![
+            params(|p| {
+                p.constrast = Some(30.0);
+                p.saturation = Some(25.0);
+            }),
+            Operation::Vignette {
+                strength: 0.6,
+                radius: 0.3,
+            },
+        ],
+        "vivid" => vec![params(|p| {
+            p.vibrance = Some(40.0);
+            p.constrast = Some(10.0);
+            p.sharpen = Some(0.3);
+        })],
+        _ => {
+            error!("Unknown filter preset : {}", name);
+            return Err(ProcessingError::from(ErrorCode::UnknownPreset).with_detail("name", name));
+        }
+    };
+    Ok(operations)
+}
+
+/// Apply a preset, `intensity` mixes the original (0) and the filtered image (1)
+pub fn apply_preset(
+    img: DynamicImage,
+    name: &str,
+    intensity: f32,
+) -> Result<DynamicImage, ProcessingError> {
+    let operations = preset_operations(name)?;
+    let intensity = intensity.clamp(0.0, 1.0);
+    if intensity == 0.0 {
+        trace!("Preset {} with intensity 0", name);
+        return Ok(img);
+    }
+
+    let filtered = operations
+        .iter()
+        .try_fold(img.clone(), |img, operation| operation.apply(img))?;
+    info!("Preset {} applied, intensity = {}", name, intensity);
+    if intensity >= 1.0 {
+        return Ok(filtered);
+    }
+
+    let mut buffer = image_adjustments::to_working(&img, false);
+    let filtered = filtered.to_rgba32f();
+    for (pixel, target) in buffer.pixels_mut().zip(filtered.pixels()) {
+        for c in 0..4 {
+            pixel[c] += (target[c] - pixel[c]) * intensity;
+        }
+    }
+    Ok(image_adjustments::from_working(buffer, &img, false))
+}
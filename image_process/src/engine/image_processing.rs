@@ -1,64 +1,84 @@
 use super::{
     image_filters::{self, ColorRgba, FilterPixelType, GradientDirection},
-    ErrorCode, ImageProcessingResult, image_processing_result::ImageDimension,
+    ErrorCode, ProcessingError, ImageProcessingResult, image_processing_result::{ImageDimension, ImageSize}, OutputOptions,
+    image_adjustments::{self, Adjustment}, image_animation, image_blend, image_compression, image_input, image_limits, image_metadata, image_orientation, image_presets, Animation, AnimationFrame, ContactSheetOptions, DecodeLimits, EmbeddedMetadata, ImageInfo, BlendMode, CompressionOptions, CompressionResult, ColorPalette, Curves, Gradient, Levels, Lut3D, LutInterpolation, Operation, Pipeline, ResizeOptions, Interpolation,
 };
 use chrono::Local;
-use image::{Rgba, imageops};
-use image::{DynamicImage};
+use image::error::{ImageError, ImageFormatHint};
+use image::{DynamicImage, ImageFormat, Rgba32FImage};
 use imageproc::drawing::Canvas;
 use log::*;
-use std::{fmt::Display, io::Cursor};
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::io::Cursor;
+use std::fmt::Display;
 use wasm_bindgen::prelude::*;
 
 //Image encoding pass in parameters
 pub trait InputType {
-    fn to_byte(&self) -> Result<Vec<u8>, ErrorCode>;
+    fn to_byte(&self) -> Result<Vec<u8>, ProcessingError>;
 }
-//InputType base64
+//InputType base64 or data URL
 impl InputType for String {
-    fn to_byte(&self) -> Result<Vec<u8>, ErrorCode> {
-        match base64::decode(ImageProcess::parse_base64_input_if_needed(&self)) {
-            Ok(img_bytes) => {
-                trace!("Convert Vec<u8> byte from base64 image");
-                return Ok(img_bytes);
-            }
-            Err(e) => {
-                error!("Failed base64::decode() : {}", e);
-                return Err(ErrorCode::UnableToDecode);
-            }
-        }
+    fn to_byte(&self) -> Result<Vec<u8>, ProcessingError> {
+        let img_bytes = image_input::decode_string(self)?;
+        trace!("Convert Vec<u8> byte from base64 image");
+        Ok(img_bytes)
     }
 }
 
 //InputType bytes
 impl InputType for Vec<u8> {
-    fn to_byte(&self) -> Result<Vec<u8>, ErrorCode> {
+    fn to_byte(&self) -> Result<Vec<u8>, ProcessingError> {
         Ok(self.to_vec())
     }
 }
 
 impl InputType for ImageProcessingResult {
-    fn to_byte(&self) -> Result<Vec<u8>, ErrorCode> {
-        Ok(self.to_byte())
+    fn to_byte(&self) -> Result<Vec<u8>, ProcessingError> {
+        Ok(self.as_bytes().to_vec())
     }
 }
 
 #[derive(Debug)]
 pub struct ImageProcess {
     pub input: Vec<u8>,
+    //Rotate / flip the decoded image according to its EXIF orientation
+    pub auto_orient: bool,
+    //Checked before decoding
+    pub limits: DecodeLimits,
 }
 
 //Can be instanciate from Typescript
 #[wasm_bindgen]
-#[derive(Debug)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ImageParameters {
-    //add colorops::invert, colorops::BiLevel, colorops::index_colors, https://docs.rs/image/latest/image/imageops/enum.FilterType.html
     pub brighten: Option<i32>,
     pub hue: Option<i32>,
     pub blur: Option<f32>,
     pub constrast: Option<f32>,
     pub grayscale: Option<bool>,
     pub invert: Option<bool>,
+    //From -100 to 100
+    pub saturation: Option<f32>,
+    pub vibrance: Option<f32>,
+    //In stops, +1 doubles the light
+    pub exposure: Option<f32>,
+    //1 keeps the image
+    pub gamma: Option<f32>,
+    //White balance, from -100 (cool / green) to 100 (warm / magenta)
+    pub temperature: Option<f32>,
+    pub tint: Option<f32>,
+    //From -100 to 100
+    pub highlights: Option<f32>,
+    pub shadows: Option<f32>,
+    //Unsharp mask strength, 1 adds the details once
+    pub sharpen: Option<f32>,
+    //Process the pixels in linear light instead of gamma encoded sRGB, false by default
+    pub linear_light: Option<bool>,
+    //Adjustments applied first, the others follow in `Adjustment::DEFAULT_ORDER`
+    #[wasm_bindgen(skip)]
+    pub order: Option<Vec<Adjustment>>,
 }
 
 #[wasm_bindgen]
@@ -67,10 +87,33 @@ impl ImageParameters {
     pub fn new() -> ImageParameters {
         ImageParameters::default()
     }
+
+    /// Adjustment names (e.g. "CONTRAST") to apply first, in this order
+    pub fn set_order(&mut self, names: Vec<String>) -> Result<(), JsValue> {
+        let order = names
+            .iter()
+            .map(|name| {
+                Adjustment::from_name(name).ok_or_else(|| {
+                    error!("Unknown adjustment : {}", name);
+                    ProcessingError::from(ErrorCode::InvalidParsing).with_detail("adjustment", name)
+                })
+            })
+            .collect::<Result<Vec<Adjustment>, ProcessingError>>()?;
+        self.order = Some(order);
+        Ok(())
+    }
+
+    /// Complete order in which the adjustments are applied
+    pub fn get_order(&self) -> Vec<String> {
+        self.processing_order()
+            .iter()
+            .map(|adjustment| adjustment.name().to_string())
+            .collect()
+    }
 }
 
-impl ImageParameters {
-    pub fn default() -> Self {
+impl Default for ImageParameters {
+    fn default() -> Self {
         Self {
             brighten: Some(0),
             blur: Some(0.0),
@@ -78,76 +121,272 @@ impl ImageParameters {
             constrast: Some(0.0),
             grayscale: Some(false),
             invert: Some(false),
+            saturation: None,
+            vibrance: None,
+            exposure: None,
+            gamma: None,
+            temperature: None,
+            tint: None,
+            highlights: None,
+            shadows: None,
+            sharpen: None,
+            linear_light: None,
+            order: None,
         }
     }
+}
 
-    /// Perform basic filter on image
-    pub fn apply_filter(&self, mut img: DynamicImage) -> DynamicImage {
-        if let Some(brighten) = self.brighten {
-            img = img.brighten(brighten);
-            trace!("Brighten filter applied : {}", brighten);
-        }
+impl ImageParameters {
+    pub fn processing_order(&self) -> Vec<Adjustment> {
+        Adjustment::complete_order(self.order.as_deref().unwrap_or(&[]))
+    }
 
-        if let Some(blur) = self.blur {
-            img = img.blur(blur);
-            trace!("Blur filter applied : {}", blur);
+    /// The adjustment is unset or its value leaves the image unchanged
+    pub fn is_identity(&self, adjustment: Adjustment) -> bool {
+        let zero = |value: Option<f32>| value.unwrap_or(0.0) == 0.0;
+        match adjustment {
+            Adjustment::EXPOSURE => zero(self.exposure),
+            Adjustment::WHITE_BALANCE => zero(self.temperature) && zero(self.tint),
+            Adjustment::HIGHLIGHTS_SHADOWS => zero(self.highlights) && zero(self.shadows),
+            Adjustment::BRIGHTEN => self.brighten.unwrap_or(0) == 0,
+            Adjustment::CONTRAST => zero(self.constrast),
+            Adjustment::GAMMA => self.gamma.unwrap_or(1.0) == 1.0,
+            Adjustment::SATURATION => zero(self.saturation),
+            Adjustment::VIBRANCE => zero(self.vibrance),
+            Adjustment::HUE => self.hue.unwrap_or(0) % 360 == 0,
+            Adjustment::GRAYSCALE => !self.grayscale.unwrap_or(false),
+            Adjustment::INVERT => !self.invert.unwrap_or(false),
+            //The image crate blurs with a sigma of 1 when it is not positive
+            Adjustment::BLUR => self.blur.unwrap_or(0.0) <= 0.0,
+            Adjustment::SHARPEN => zero(self.sharpen),
         }
+    }
 
-        if let Some(hue) = self.hue {
-            img = img.huerotate(hue);
-            trace!("Huerotate filter applied : {}", hue);
+    /// Perform the adjustments on the image, by default in this order :
+    ///     1. exposure, white balance (temperature / tint), highlights / shadows
+    ///     2. brighten, blur
+    ///     3. saturation, vibrance, hue, grayscale
+    ///     4. contrast, gamma, invert, sharpen
+    /// The adjustments listed in `order` run first. The unset or identity ones are skipped,
+    /// the image is returned untouched when none is left
+    pub fn apply_filter(&self, img: DynamicImage) -> DynamicImage {
+        let steps: Vec<Adjustment> = self
+            .processing_order()
+            .into_iter()
+            .filter(|adjustment| !self.is_identity(*adjustment))
+            .collect();
+        if steps.is_empty() {
+            trace!("No adjustment to apply");
+            return img;
         }
 
-        if let Some(b) = self.grayscale {
-            if b {
-                img = img.grayscale();
-                trace!("Grayscale filter applied");
-            }
+        let linear = self.linear_light.unwrap_or(false);
+        let mut buffer = image_adjustments::to_working(&img, linear);
+        for adjustment in &steps {
+            buffer = self.apply_adjustment(*adjustment, buffer, linear);
+            trace!("{} applied", adjustment.name());
         }
-
-        if let Some(constrast) = self.constrast {
-            img = img.adjust_contrast(constrast);
-            trace!("Constrast filter applied : {}", constrast);
+        let adjusted = image_adjustments::from_working(buffer, &img, linear);
+
+        //Keep a grayscale output unless the white balance tints it afterwards
+        let gray = steps
+            .iter()
+            .rposition(|adjustment| *adjustment == Adjustment::GRAYSCALE)
+            .is_some_and(|index| !steps[index..].contains(&Adjustment::WHITE_BALANCE));
+        match adjusted {
+            DynamicImage::ImageRgba8(_) | DynamicImage::ImageRgba16(_) if gray => {
+                adjusted.grayscale()
+            }
+            _ => adjusted,
         }
+    }
 
-        if self.invert.unwrap_or(false) {
-            img.invert();
-            trace!("Invert filter applied");
+    fn apply_adjustment(
+        &self,
+        adjustment: Adjustment,
+        mut buffer: Rgba32FImage,
+        linear: bool,
+    ) -> Rgba32FImage {
+        let map = |buffer: &mut Rgba32FImage, func: &dyn Fn([f32; 3]) -> [f32; 3]| {
+            image_adjustments::map_pixels(buffer, func)
+        };
+        match adjustment {
+            Adjustment::EXPOSURE => map(
+                &mut buffer,
+                &image_adjustments::exposure(self.exposure.unwrap_or(0.0), linear),
+            ),
+            Adjustment::WHITE_BALANCE => map(
+                &mut buffer,
+                &image_adjustments::white_balance(
+                    self.temperature.unwrap_or(0.0),
+                    self.tint.unwrap_or(0.0),
+                ),
+            ),
+            Adjustment::HIGHLIGHTS_SHADOWS => map(
+                &mut buffer,
+                &image_adjustments::highlights_shadows(
+                    self.highlights.unwrap_or(0.0),
+                    self.shadows.unwrap_or(0.0),
+                ),
+            ),
+            Adjustment::BRIGHTEN => map(
+                &mut buffer,
+                &image_adjustments::brighten(self.brighten.unwrap_or(0)),
+            ),
+            Adjustment::CONTRAST => map(
+                &mut buffer,
+                &image_adjustments::contrast(self.constrast.unwrap_or(0.0)),
+            ),
+            Adjustment::GAMMA => map(
+                &mut buffer,
+                &image_adjustments::gamma(self.gamma.unwrap_or(1.0)),
+            ),
+            Adjustment::SATURATION => map(
+                &mut buffer,
+                &image_adjustments::saturation(self.saturation.unwrap_or(0.0)),
+            ),
+            Adjustment::VIBRANCE => map(
+                &mut buffer,
+                &image_adjustments::vibrance(self.vibrance.unwrap_or(0.0)),
+            ),
+            Adjustment::HUE => map(&mut buffer, &image_adjustments::hue(self.hue.unwrap_or(0))),
+            Adjustment::GRAYSCALE => map(&mut buffer, &image_adjustments::grayscale()),
+            Adjustment::INVERT => map(&mut buffer, &image_adjustments::invert()),
+            Adjustment::BLUR => {
+                return image_adjustments::blur(&buffer, self.blur.unwrap_or(0.0));
+            }
+            Adjustment::SHARPEN => {
+                return image_adjustments::sharpen(&buffer, self.sharpen.unwrap_or(0.0));
+            }
         }
-
-        img
+        buffer
     }
 }
 
 impl ImageProcess {
-    pub fn new<T>(input: T) -> Result<ImageProcess, ErrorCode>
+    pub fn new<T>(input: T) -> Result<ImageProcess, ProcessingError>
     where
         T: InputType,
     {
         trace!("New ImageProcess instance");
         Ok(ImageProcess {
             input: input.to_byte()?,
+            auto_orient: image_orientation::auto_orient(),
+            limits: image_limits::decode_limits(),
         })
     }
 
-    ///Replace the "data:image/jpeg;base64," in the string
-    pub fn parse_base64_input_if_needed(base64_input: &String) -> String {
-        str::replace(base64_input.as_str(), "data:image/png;base64,", "")
+    /// Keep the pixels as stored when `auto_orient` is false
+    pub fn with_auto_orient(mut self, auto_orient: bool) -> ImageProcess {
+        self.auto_orient = auto_orient;
+        self
+    }
+
+    /// Decode with other limits than the global ones
+    pub fn with_limits(mut self, limits: DecodeLimits) -> ImageProcess {
+        self.limits = limits;
+        self
+    }
+
+    /// Format, dimensions, color type, ICC and EXIF read from the headers only
+    pub fn get_image_info(&self) -> Result<ImageInfo, ProcessingError> {
+        image_metadata::read_info(&self.input)
+    }
+
+    /// EXIF orientation of the input, from 1 (upright) to 8
+    pub fn get_orientation(&self) -> u32 {
+        image_orientation::read_orientation(&self.input)
+    }
+
+    ///Remove the "data:image/jpeg;base64," header of the string, whatever the MIME type
+    pub fn parse_base64_input_if_needed(base64_input: &str) -> String {
+        image_input::strip_data_url(base64_input).to_string()
     }
 
     /// Create a Dynamic image from bytes
-    fn get_dynamic_image(&self) -> Result<DynamicImage, ErrorCode> {
+    pub fn get_dynamic_image(&self) -> Result<DynamicImage, ProcessingError> {
         trace!("Try to create Dynamic image from byte");
-        match image::load_from_memory(&self.input.as_slice()) {
-            Ok(dynamic_image) => {
-                trace!("Dynamic image instance created");
-                Ok(dynamic_image)
+        //Headers the crate cannot read are left to the decoder and its own limits
+        let mut animated_webp = false;
+        if let Ok((format, width, height, color_type)) =
+            image_metadata::read_format_header(&self.input)
+        {
+            self.limits
+                .check(format, width, height, color_type.bytes_per_pixel(), &self.input)?;
+            animated_webp =
+                format == ImageFormat::WebP && image_animation::is_animated(format, &self.input);
+        }
+        let dynamic_image = if animated_webp {
+            //Each frame is checked against the canvas and the limits, only the first is decoded
+            image_animation::webp_first_frame(&self.input, &self.limits)?
+        } else {
+            let mut reader = image::io::Reader::new(Cursor::new(self.input.as_slice()));
+            reader.limits(self.limits.image_limits());
+            let decoded = reader
+                .with_guessed_format()
+                .map_err(ImageError::IoError)
+                .and_then(|reader| reader.decode());
+            match decoded {
+                Ok(dynamic_image) => dynamic_image,
+                Err(e) => {
+                    error!("Unable to create dynamic image from byte : {}", e);
+                    return Err(self.decode_error(e));
+                }
             }
-            Err(e) => {
-                error!("Unable to create dynamic image from byte : {}", e);
-                Err(ErrorCode::UnableToDecode)
+        };
+        trace!("Dynamic image instance created");
+        if self.auto_orient {
+            Ok(image_orientation::apply_orientation(dynamic_image, self.get_orientation()))
+        } else {
+            Ok(dynamic_image)
+        }
+    }
+
+    /// Every frame of an animated GIF, APNG or WebP, None for a still image
+    pub fn get_animation(&self) -> Result<Option<Animation>, ProcessingError> {
+        let (format, width, height, color_type) =
+            match image_metadata::read_format_header(&self.input) {
+                Ok(header) => header,
+                Err(_) => return Ok(None),
+            };
+        if !image_animation::is_animated(format, &self.input) {
+            return Ok(None);
+        }
+        self.limits
+            .check(format, width, height, color_type.bytes_per_pixel(), &self.input)?;
+        Animation::decode(format, &self.input, &self.limits).map(Some)
+    }
+
+    /// Number of frames read from the headers, 1 for a still image
+    pub fn get_frame_count(&self) -> Result<u32, ProcessingError> {
+        let (format, ..) = image_metadata::read_format_header(&self.input)?;
+        Ok(image_metadata::frame_count(format, &self.input))
+    }
+
+    //Error of the decoder with the format and the size of the input
+    fn decode_error(&self, e: ImageError) -> ProcessingError {
+        let code = match e {
+            ImageError::Limits(_) => ErrorCode::LimitExceeded,
+            _ => ErrorCode::UnableToDecode,
+        };
+        let mut error = ProcessingError::from(code).with_detail("input_bytes", self.input.len());
+        if let Ok(format) = image::guess_format(&self.input) {
+            error = error.with_detail("format", format!("{:?}", format));
+        }
+        match &e {
+            ImageError::Unsupported(unsupported) => match unsupported.format_hint() {
+                ImageFormatHint::Exact(format) => {
+                    error.with_detail("unsupported_format", format!("{:?}", format))
+                }
+                ImageFormatHint::Name(name) => error.with_detail("unsupported_format", name),
+                _ => error,
+            },
+            ImageError::IoError(io) if io.kind() == std::io::ErrorKind::UnexpectedEof => {
+                error.with_detail("truncated", true)
             }
+            _ => error,
         }
+        .with_cause(e)
     }
 
     pub fn as_byte(&self) -> Vec<u8> {
@@ -158,34 +397,43 @@ impl ImageProcess {
         base64::encode(&self.input)
     }
 
-    pub fn get_image_dimension(&self) -> Result<ImageDimension, ErrorCode> {
+    pub fn get_image_dimension(&self) -> Result<ImageDimension, ProcessingError> {
         let d = self.get_dynamic_image()?.dimensions();
         Ok(ImageDimension::new(d.0, d.1))
     }
 
-    /// Convert Dynamic image to bytes
-    fn dynamic_image_to_byte(img: &DynamicImage) -> Vec<u8> {
-        trace!("Convert image to bytes");
-        let mut edited_image_bytes = Vec::new();
-        img.write_to(
-            &mut Cursor::new(&mut edited_image_bytes),
-            image::ImageOutputFormat::Png,
-        )
-        .unwrap();
+    /// Metadata of the input, to be copied in the output
+    pub fn get_embedded_metadata(&self) -> EmbeddedMetadata {
+        EmbeddedMetadata::read(&self.input, self.auto_orient)
+    }
 
-        edited_image_bytes
+    /// Convert Dynamic image to bytes, with the metadata of the input kept by the options
+    fn dynamic_image_to_byte(
+        &self,
+        img: &DynamicImage,
+        output: &OutputOptions,
+    ) -> Result<Vec<u8>, ProcessingError> {
+        if output.metadata.is_strip_all() {
+            output.encode(img)
+        } else {
+            output.encode_with_metadata(img, &self.get_embedded_metadata())
+        }
     }
 
     /// Save the image on the specific location
-    pub fn save_image(img: &DynamicImage, path: &str) -> Option<ErrorCode> {
+    pub fn save_image(img: &DynamicImage, path: &str) -> Option<ProcessingError> {
         let local_date = Local::now();
         let date_string = local_date.format("%Y-%m-%d_%H-%M-%S").to_string();
         let full_file_path = format!("{}image_save_{}.png", &path, &date_string);
 
         println!("Full path to save : {}", &full_file_path);
 
-        if let Err(_) = img.save(full_file_path) {
-            return Some(ErrorCode::UnableToSave);
+        if let Err(e) = img.save(&full_file_path) {
+            return Some(
+                ProcessingError::from(ErrorCode::UnableToSave)
+                    .with_cause(e)
+                    .with_detail("path", full_file_path),
+            );
         }
         None
     }
@@ -193,36 +441,30 @@ impl ImageProcess {
     pub fn compute_parameters(
         &self,
         params: ImageParameters,
-    ) -> Result<ImageProcessingResult, ErrorCode> {
-        let mut img = self.get_dynamic_image()?;
-
-        img = params.apply_filter(img.clone());
-
-        Ok(ImageProcessingResult::new(
-            ImageProcess::dynamic_image_to_byte(&img),
-        ))
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_filters(output, |img| Ok(params.apply_filter(img)))
     }
 
-    pub fn compute_filter_sobel(&self) -> Result<ImageProcessingResult, ErrorCode> {
-        self.compute_filters(|| {
-            image_filters::filter_sobel(
-                self.get_dynamic_image()?,
-            )
-        })
+    pub fn compute_filter_sobel(
+        &self,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(&Operation::Sobel, output)
     }
 
     pub fn compute_filter_band_color(
         &self,
-        colors: Vec<ColorRgba>,
-        direction: GradientDirection
-    ) -> Result<ImageProcessingResult, ErrorCode> {
-        self.compute_filters(|| {
-            image_filters::filter_band_color(
-                &mut self.get_dynamic_image()?,
-                colors.iter().map(|c| Rgba::<u8>::from(*c)).collect(),
-                direction
-            )
-        })
+        palette: Option<ColorPalette>,
+        direction: GradientDirection,
+        softness: Option<f32>,
+        blend_mode: Option<BlendMode>,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(
+            &Operation::band_color(palette, direction, softness, blend_mode),
+            output,
+        )
     }
 
     pub fn compute_filter_gradient(
@@ -230,14 +472,64 @@ impl ImageProcess {
         start: ColorRgba,
         to: ColorRgba,
         gradient: GradientDirection,
-    ) -> Result<ImageProcessingResult, ErrorCode> {
-        self.compute_filters(|| {
-            image_filters::filter_gradient(
-                &mut self.get_dynamic_image()?,
-                start.into(),
-                to.into(),
-                gradient,
-            )
+        blend_mode: Option<BlendMode>,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(
+            &Operation::Gradient {
+                from: start,
+                to,
+                direction: gradient,
+                blend_mode: blend_mode.unwrap_or_default(),
+            },
+            output,
+        )
+    }
+
+    pub fn compute_curves(
+        &self,
+        curves: &Curves,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_filters(output, |img| Ok(curves.apply(img)))
+    }
+
+    pub fn compute_levels(
+        &self,
+        levels: Levels,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_filters(output, |img| Ok(levels.apply(img)))
+    }
+
+    pub fn compute_lut(
+        &self,
+        lut: &Lut3D,
+        interpolation: Option<LutInterpolation>,
+        intensity: Option<f32>,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(&Operation::lut(lut, interpolation, intensity), output)
+    }
+
+    pub fn compute_preset(
+        &self,
+        name: &str,
+        intensity: Option<f32>,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        //Fail before decoding on an unknown name
+        image_presets::preset_operations(name)?;
+        self.compute_operation(&Operation::preset(name, intensity), output)
+    }
+
+    pub fn compute_filter_custom_gradient(
+        &self,
+        gradient: &Gradient,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_filters(output, |mut img| {
+            image_filters::filter_custom_gradient(&mut img, gradient)
         })
     }
 
@@ -245,78 +537,253 @@ impl ImageProcess {
         &self,
         pixel_filter: FilterPixelType,
         color: ColorRgba,
-    ) -> Result<ImageProcessingResult, ErrorCode> {
-        self.compute_filters(|| {
-            image_filters::filter_pixel(&mut self.get_dynamic_image()?, pixel_filter, color)
+        spacing: Option<u32>,
+        thickness: Option<u32>,
+        blend_mode: Option<BlendMode>,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(
+            &Operation::pixel(pixel_filter, color, spacing, thickness, blend_mode),
+            output,
+        )
+    }
+
+    /// Composite the `layer` image at (x, y) on the top of this one
+    pub fn compute_composite(
+        &self,
+        layer: &ImageProcess,
+        x: i64,
+        y: i64,
+        opacity: f32,
+        blend_mode: BlendMode,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        let mut top = layer.get_dynamic_image()?.to_rgba8();
+        let opacity = opacity.clamp(0.0, 1.0);
+        if opacity < 1.0 {
+            for pixel in top.pixels_mut() {
+                pixel[3] = (pixel[3] as f32 * opacity).round() as u8;
+            }
+        }
+        self.compute_filters(output, |mut img| {
+            image_blend::blend_layer(&mut img, &top, x, y, blend_mode);
+            Ok(img)
         })
     }
 
-    /// Perform the filter function
-    fn compute_filters<F>(&self, func: F) -> Result<ImageProcessingResult, ErrorCode>
+    /// Decode, apply a single operation and encode
+    pub fn compute_operation(
+        &self,
+        operation: &Operation,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_filters(output, |img| operation.apply(img))
+    }
+
+    /// Decode once, run every operation of the pipeline, then encode once
+    pub fn compute_pipeline(
+        &self,
+        pipeline: &Pipeline,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_filters(output, |img| pipeline.apply(img))
+    }
+
+    /// Perform the filter function, on every frame when the input is animated
+    /// and the output format can hold an animation
+    fn compute_filters<F>(
+        &self,
+        output: OutputOptions,
+        func: F,
+    ) -> Result<ImageProcessingResult, ProcessingError>
     where
-        F: Fn() -> Result<DynamicImage, ErrorCode>,
+        F: Fn(DynamicImage) -> Result<DynamicImage, ProcessingError>,
     {
+        if output.keep_animation && output.format.supports_animation() {
+            if let Some(animation) = self.get_animation()? {
+                return Ok(ImageProcessingResult::new(
+                    output.encode_animation(
+                        &animation.map_frames(func)?,
+                        &self.get_embedded_metadata(),
+                    )?,
+                    output.format,
+                ));
+            }
+        }
         Ok(ImageProcessingResult::new(
-            ImageProcess::dynamic_image_to_byte(&func()?),
+            self.dynamic_image_to_byte(&func(self.get_dynamic_image()?)?, &output)?,
+            output.format,
         ))
     }
 
-    pub fn resize(&self, width: u32, height: u32) -> Result<ImageProcessingResult, ErrorCode> {
+    /// Encode a single frame of the animation as a still image, 0 is the only frame of a still image
+    pub fn extract_frame(
+        &self,
+        index: u32,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        let img = match self.get_animation()? {
+            Some(animation) => animation.frame(index)?.image.clone(),
+            None if index == 0 => self.get_dynamic_image()?,
+            None => {
+                return Err(ProcessingError::from(ErrorCode::FrameNotFound)
+                    .with_detail("index", index)
+                    .with_detail("frame_count", 1))
+            }
+        };
         Ok(ImageProcessingResult::new(
-            ImageProcess::dynamic_image_to_byte(&self.get_dynamic_image()?.resize(
-                width,
-                height,
-                imageops::FilterType::Lanczos3,
-            )),
+            self.dynamic_image_to_byte(&img, &output)?,
+            output.format,
         ))
     }
 
-    /// Return the bytes size of the base64 image
-    /// Formula : x = (n * (3/4)) - y
-    ///     1. x is the size of a file in bytes
-    ///     2. n is the length of the Base64 String
-    ///     3. y will be 2 if Base64 ends with '==' and 1 if Base64 ends with '='.
-    pub fn get_image_weight_byte(base64_input: String) -> Result<usize, ErrorCode> {
-        let clean_base64 = ImageProcess::parse_base64_input_if_needed(&base64_input);
-        let length = clean_base64.len();
-        let output_padding = if clean_base64.ends_with("==") { 1 } else if clean_base64.ends_with("=") { 2 } else { 0 };
+    /// Every frame of the animation in a grid
+    pub fn contact_sheet(
+        &self,
+        options: ContactSheetOptions,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        let animation = match self.get_animation()? {
+            Some(animation) => animation,
+            None => Animation::new(
+                vec![AnimationFrame {
+                    image: self.get_dynamic_image()?,
+                    delay_ms: 0,
+                }],
+                1,
+            )?,
+        };
+        let sheet = animation.contact_sheet(&options, &self.limits)?;
+        Ok(ImageProcessingResult::new(
+            self.dynamic_image_to_byte(&sheet, &output)?,
+            output.format,
+        ))
+    }
 
-        trace!("base64 image [{}...{}]", &clean_base64[0..20], &clean_base64[(clean_base64.len() - 20)..]);
-        trace!("base64 length = {}", length);
-        trace!("output_padding = {}", output_padding);
+    pub fn resize(
+        &self,
+        width: u32,
+        height: u32,
+        options: ResizeOptions,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(
+            &Operation::Resize {
+                width,
+                height,
+                options,
+            },
+            output,
+        )
+    }
 
-        if length == 0 || clean_base64.is_empty() {
-            return Err(ErrorCode::ImageEmpty);
-        }
+    /// Keep the `width` * `height` area starting at (x, y)
+    pub fn crop(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(
+            &Operation::Crop {
+                x,
+                y,
+                width,
+                height,
+            },
+            output,
+        )
+    }
 
-        let size_in_byte = (length as f64 * (3 as f64 / 4 as f64)) as usize - output_padding;
-        trace!("\nImage size : {} bytes", size_in_byte);
-        Ok(size_in_byte)
+    pub fn rotate90(&self, output: OutputOptions) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(&Operation::Rotate90, output)
     }
 
-    /// We try to have an image < 200 kbytes
-    pub fn calc_best_size_ratio(base64_input: String, target_size: usize) -> Result<ImageProcessingResult, ErrorCode> {
+    pub fn rotate180(&self, output: OutputOptions) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(&Operation::Rotate180, output)
+    }
 
-        let mut img = ImageProcess::new(base64_input)?;
+    pub fn rotate270(&self, output: OutputOptions) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(&Operation::Rotate270, output)
+    }
 
-        let mut image_size = ImageProcess::get_image_weight_byte(img.to_base64())?;
-        info!("Image_size = {} (target size = {})", image_size, target_size);
+    pub fn flip_horizontal(
+        &self,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(&Operation::FlipHorizontal, output)
+    }
 
-        while image_size > target_size {
-            let (width, height) = img.get_dynamic_image()?.dimensions();
-            let (new_width, new_height) = ((width * 90 / 100), (height * 90 / 100));
-            // info!("[Calc best size ratio] Image size = {} | Image dimension = {}x{} | New image dimension = {}x{}", image_size, width, height, new_width, new_height);
+    pub fn flip_vertical(&self, output: OutputOptions) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(&Operation::FlipVertical, output)
+    }
 
-            let result = img.resize(new_width, new_height)?;
+    /// Rotate by `angle` degrees clockwise
+    pub fn rotate(
+        &self,
+        angle: f32,
+        interpolation: Option<Interpolation>,
+        fill: Option<ColorRgba>,
+        expand: Option<bool>,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        self.compute_operation(
+            &Operation::rotate(angle, interpolation, fill, expand),
+            output,
+        )
+    }
 
-            img = ImageProcess::new(result)?;
+    /// Return the bytes size of the base64 image or data URL, without decoding it
+    pub fn get_image_weight_byte(input: &str) -> Result<usize, ProcessingError> {
+        image_input::encoded_length(input)
+    }
 
-            image_size = ImageProcess::get_image_weight_byte(img.to_base64())?;
-        }
-        Ok(ImageProcessingResult::new(
-            ImageProcess::dynamic_image_to_byte(&img.get_dynamic_image()?),
+    /// Encoded size, pixel buffer size and bits per pixel, only the header is decoded
+    pub fn get_image_size(&self) -> Result<ImageSize, ProcessingError> {
+        let (_, width, height, color_type) = image_metadata::read_format_header(&self.input)?;
+        let bits_per_pixel = color_type.bits_per_pixel();
+        let decoded_bytes = (width as u64 * height as u64 * bits_per_pixel as u64).div_ceil(8);
+        trace!(
+            "Image size : {} bytes encoded, {} bytes decoded ({} bpp)",
+            self.input.len(),
+            decoded_bytes,
+            bits_per_pixel
+        );
+        Ok(ImageSize::new(
+            self.input.len(),
+            usize::try_from(decoded_bytes).unwrap_or(usize::MAX),
+            bits_per_pixel,
         ))
     }
+
+    /// Biggest and best looking image of at most `target_size` bytes
+    pub fn compress_to_size(
+        &self,
+        target_size: usize,
+        options: CompressionOptions,
+        output: OutputOptions,
+    ) -> Result<CompressionResult, ProcessingError> {
+        image_compression::compress_to_size(
+            &self.get_dynamic_image()?,
+            &self.get_embedded_metadata(),
+            target_size,
+            options,
+            output,
+        )
+    }
+
+    /// Image of at most `target_size` bytes with the default compression options
+    pub fn calc_best_size_ratio<T: InputType>(
+        input: T,
+        target_size: usize,
+        output: OutputOptions,
+    ) -> Result<ImageProcessingResult, ProcessingError> {
+        Ok(ImageProcess::new(input)?
+            .compress_to_size(target_size, CompressionOptions::default(), output)?
+            .into_result())
+    }
 }
 
 impl Display for ImageProcess {
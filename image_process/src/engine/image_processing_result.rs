@@ -1,10 +1,13 @@
+use super::OutputFormat;
+use js_sys::Uint8Array;
 use wasm_bindgen::prelude::*;
 
 /// Result structure after image processing has been apply
 #[wasm_bindgen]
 #[derive(Debug, Clone)]
 pub struct ImageProcessingResult {
-    result: Vec<u8>
+    result: Vec<u8>,
+    format: OutputFormat,
 }
 
 #[wasm_bindgen]
@@ -13,14 +16,54 @@ impl ImageProcessingResult {
         self.result.clone()
     }
 
+    /// View of the bytes in the WASM memory as a JS `Uint8Array`, without copy
+    /// The view is only valid while this result is alive and until the next allocation
+    /// in WASM (any call may grow the memory and detach it) : pass it straight to
+    /// `new Blob([bytes])`, or use `take_bytes` to keep the bytes
+    pub fn to_uint8array(&self) -> Uint8Array {
+        //Safety : no allocation happens before the view is handed to JS
+        unsafe { Uint8Array::view(&self.result) }
+    }
+
+    /// Move the bytes out of the result, unlike `to_byte` the buffer is not cloned
+    pub fn take_bytes(self) -> Vec<u8> {
+        self.result
+    }
+
     pub fn to_base64(&self) -> String {
         base64::encode(&self.result)
     }
+
+    /// Ex : `data:image/png;base64,iVBORw0KGgo...`
+    pub fn to_data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime_type(), self.to_base64())
+    }
+
+    pub fn get_format(&self) -> OutputFormat {
+        self.format
+    }
+
+    pub fn mime_type(&self) -> String {
+        self.format.mime_type().to_string()
+    }
+
+    /// File extension without the dot
+    pub fn extension(&self) -> String {
+        self.format.extension().to_string()
+    }
+
+    pub fn byte_length(&self) -> usize {
+        self.result.len()
+    }
 }
 
 impl ImageProcessingResult {
-    pub fn new(result: Vec<u8>) -> ImageProcessingResult {
-        ImageProcessingResult { result }
+    pub fn new(result: Vec<u8>, format: OutputFormat) -> ImageProcessingResult {
+        ImageProcessingResult { result, format }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.result
     }
 }
 
@@ -43,4 +86,33 @@ impl ImageDimension {
     pub fn get_height(&self) -> usize {
         self.height
     }
-}
\ No newline at end of file
+}
+
+/// Weight of the image, encoded and once decoded in memory
+#[wasm_bindgen]
+pub struct ImageSize {
+    encoded_bytes: usize,
+    decoded_bytes: usize,
+    bits_per_pixel: u16,
+}
+
+#[wasm_bindgen]
+impl ImageSize {
+    pub fn new(encoded_bytes: usize, decoded_bytes: usize, bits_per_pixel: u16) -> ImageSize {
+        ImageSize { encoded_bytes, decoded_bytes, bits_per_pixel }
+    }
+
+    /// Size of the file
+    pub fn get_encoded_bytes(&self) -> usize {
+        self.encoded_bytes
+    }
+
+    /// Size of the pixel buffer : width * height * bits per pixel / 8
+    pub fn get_decoded_bytes(&self) -> usize {
+        self.decoded_bytes
+    }
+
+    pub fn get_bits_per_pixel(&self) -> u16 {
+        self.bits_per_pixel
+    }
+}
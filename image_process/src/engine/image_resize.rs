@@ -0,0 +1,193 @@
+use image::{imageops, DynamicImage, GenericImageView, RgbaImage};
+use log::*;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use super::image_filters::ColorRgba;
+
+/// How the image is fitted in the target box
+///     - FIT : keep the aspect ratio, the whole image is inside the box
+///     - FILL : keep the aspect ratio, cover the whole box and crop the overflow (CSS `cover`)
+///     - EXACT : stretch the image to the box
+///     - CONTAIN : like FIT, then pad to the box with the background color
+#[wasm_bindgen]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum ResizeMode {
+    #[default]
+    FIT,
+    FILL,
+    EXACT,
+    CONTAIN,
+}
+
+/// Anchor used to place the image when it is cropped (FILL) or padded (CONTAIN)
+#[wasm_bindgen]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum Gravity {
+    #[default]
+    CENTER,
+    NORTH,
+    SOUTH,
+    EAST,
+    WEST,
+    NORTH_EAST,
+    NORTH_WEST,
+    SOUTH_EAST,
+    SOUTH_WEST,
+}
+
+/// Resampling filter, from the fastest to the sharpest
+#[wasm_bindgen]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum ResizeFilter {
+    NEAREST,
+    TRIANGLE,
+    CATMULL_ROM,
+    GAUSSIAN,
+    #[default]
+    LANCZOS3,
+}
+
+impl From<ResizeFilter> for imageops::FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::NEAREST => imageops::FilterType::Nearest,
+            ResizeFilter::TRIANGLE => imageops::FilterType::Triangle,
+            ResizeFilter::CATMULL_ROM => imageops::FilterType::CatmullRom,
+            ResizeFilter::GAUSSIAN => imageops::FilterType::Gaussian,
+            ResizeFilter::LANCZOS3 => imageops::FilterType::Lanczos3,
+        }
+    }
+}
+
+impl Gravity {
+    /// Offset of the image in a space bigger by `free_w` * `free_h` pixels
+    fn offset(&self, free_w: u32, free_h: u32) -> (u32, u32) {
+        let x = match self {
+            Self::WEST | Self::NORTH_WEST | Self::SOUTH_WEST => 0,
+            Self::EAST | Self::NORTH_EAST | Self::SOUTH_EAST => free_w,
+            _ => free_w / 2,
+        };
+        let y = match self {
+            Self::NORTH | Self::NORTH_EAST | Self::NORTH_WEST => 0,
+            Self::SOUTH | Self::SOUTH_EAST | Self::SOUTH_WEST => free_h,
+            _ => free_h / 2,
+        };
+        (x, y)
+    }
+}
+
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(default)]
+pub struct ResizeOptions {
+    pub mode: ResizeMode,
+    pub filter: ResizeFilter,
+    pub gravity: Gravity,
+    //Padding color of CONTAIN, transparent by default
+    pub background: ColorRgba,
+    //Never enlarge the image, EXACT scales the whole box down to keep its aspect ratio
+    pub only_shrink: bool,
+}
+
+#[wasm_bindgen]
+impl ResizeOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        mode: Option<ResizeMode>,
+        filter: Option<ResizeFilter>,
+        gravity: Option<Gravity>,
+        background: Option<ColorRgba>,
+        only_shrink: Option<bool>,
+    ) -> ResizeOptions {
+        let default = ResizeOptions::default();
+        ResizeOptions {
+            mode: mode.unwrap_or_default(),
+            filter: filter.unwrap_or_default(),
+            gravity: gravity.unwrap_or_default(),
+            background: background.unwrap_or(default.background),
+            only_shrink: only_shrink.unwrap_or(default.only_shrink),
+        }
+    }
+}
+
+impl Default for ResizeOptions {
+    fn default() -> Self {
+        Self {
+            mode: ResizeMode::FIT,
+            filter: ResizeFilter::LANCZOS3,
+            gravity: Gravity::CENTER,
+            background: ColorRgba::new(0, 0, 0, 0),
+            only_shrink: false,
+        }
+    }
+}
+
+impl ResizeOptions {
+    /// Resize the image in the `width` * `height` box
+    /// A 0 side is computed from the other one to keep the aspect ratio
+    pub fn apply(&self, img: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+        let (w, h) = img.dimensions();
+        if w == 0 || h == 0 || (width == 0 && height == 0) {
+            return img.clone();
+        }
+        let ratio = w as f64 / h as f64;
+        let width = if width == 0 { scaled(height as f64 * ratio) } else { width };
+        let height = if height == 0 { scaled(width as f64 / ratio) } else { height };
+
+        let scale_x = width as f64 / w as f64;
+        let scale_y = height as f64 / h as f64;
+        let shrink = |scale: f64| if self.only_shrink { scale.min(1.0) } else { scale };
+        let filter = self.filter.into();
+        info!(
+            "Resize {}x{} to {}x{} ({:?}, {:?})",
+            w, h, width, height, self.mode, self.filter
+        );
+
+        match self.mode {
+            ResizeMode::EXACT => {
+                //With only_shrink, the whole box is scaled down until no side is enlarged
+                let scale = if self.only_shrink {
+                    scale_x.max(scale_y).max(1.0)
+                } else {
+                    1.0
+                };
+                img.resize_exact(scaled(width as f64 / scale), scaled(height as f64 / scale), filter)
+            }
+            ResizeMode::FIT => {
+                let scale = shrink(scale_x.min(scale_y));
+                img.resize_exact(scaled(w as f64 * scale), scaled(h as f64 * scale), filter)
+            }
+            ResizeMode::FILL => {
+                let scale = shrink(scale_x.max(scale_y));
+                let resized =
+                    img.resize_exact(scaled(w as f64 * scale), scaled(h as f64 * scale), filter);
+                let (crop_w, crop_h) = (width.min(resized.width()), height.min(resized.height()));
+                let (x, y) = self
+                    .gravity
+                    .offset(resized.width() - crop_w, resized.height() - crop_h);
+                resized.crop_imm(x, y, crop_w, crop_h)
+            }
+            ResizeMode::CONTAIN => {
+                let scale = shrink(scale_x.min(scale_y));
+                let resized =
+                    img.resize_exact(scaled(w as f64 * scale), scaled(h as f64 * scale), filter);
+                let mut canvas = RgbaImage::from_pixel(width, height, self.background.into());
+                let (x, y) = self.gravity.offset(
+                    width.saturating_sub(resized.width()),
+                    height.saturating_sub(resized.height()),
+                );
+                imageops::overlay(&mut canvas, &resized.to_rgba8(), x as i64, y as i64);
+                DynamicImage::ImageRgba8(canvas)
+            }
+        }
+    }
+}
+
+/// Round a computed side, never below 1 pixel
+fn scaled(side: f64) -> u32 {
+    (side.round() as u32).max(1)
+}
@@ -0,0 +1,303 @@
+use image::DynamicImage;
+use log::*;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use super::image_adjustments;
+
+//Curves and levels values use the scale of 8 bits images, whatever the bit depth
+const SCALE: f32 = 255.0;
+
+/// Channel changed by a curve, RGB is the master curve applied on the three colors
+#[wasm_bindgen]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum CurveChannel {
+    #[default]
+    RGB,
+    RED,
+    GREEN,
+    BLUE,
+    ALPHA,
+}
+
+/// Output level `y` for the input level `x`, both from 0 to 255
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+pub struct CurvePoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[wasm_bindgen]
+impl CurvePoint {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: f32, y: f32) -> CurvePoint {
+        CurvePoint {
+            x: x.clamp(0.0, SCALE),
+            y: y.clamp(0.0, SCALE),
+        }
+    }
+}
+
+/// Tone curves through control points, interpolated with monotone cubic splines
+/// so the curve never overshoots between two points.
+/// (0, 0) and (255, 255) are implied when the first / last point is not at the edge,
+/// a channel without point is unchanged. The color curves run before the master one
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Curves {
+    rgb: Vec<CurvePoint>,
+    red: Vec<CurvePoint>,
+    green: Vec<CurvePoint>,
+    blue: Vec<CurvePoint>,
+    alpha: Vec<CurvePoint>,
+}
+
+#[wasm_bindgen]
+impl Curves {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Curves {
+        Curves::default()
+    }
+
+    /// A point with a NaN or infinite coordinate is ignored
+    pub fn add_point(&mut self, channel: CurveChannel, x: f32, y: f32) {
+        if !x.is_finite() || !y.is_finite() {
+            warn!("Curve point ({}, {}) ignored", x, y);
+            return;
+        }
+        self.points_mut(channel).push(CurvePoint::new(x, y));
+    }
+
+    pub fn clear(&mut self, channel: CurveChannel) {
+        self.points_mut(channel).clear();
+    }
+
+    pub fn points_count(&self, channel: CurveChannel) -> usize {
+        self.points(channel).len()
+    }
+}
+
+impl Curves {
+    pub fn points(&self, channel: CurveChannel) -> &[CurvePoint] {
+        match channel {
+            CurveChannel::RGB => &self.rgb,
+            CurveChannel::RED => &self.red,
+            CurveChannel::GREEN => &self.green,
+            CurveChannel::BLUE => &self.blue,
+            CurveChannel::ALPHA => &self.alpha,
+        }
+    }
+
+    fn points_mut(&mut self, channel: CurveChannel) -> &mut Vec<CurvePoint> {
+        match channel {
+            CurveChannel::RGB => &mut self.rgb,
+            CurveChannel::RED => &mut self.red,
+            CurveChannel::GREEN => &mut self.green,
+            CurveChannel::BLUE => &mut self.blue,
+            CurveChannel::ALPHA => &mut self.alpha,
+        }
+    }
+
+    /// Interpolated curve of a channel, None when the channel is unchanged
+    pub fn spline(&self, channel: CurveChannel) -> Option<MonotoneSpline> {
+        MonotoneSpline::new(self.points(channel))
+    }
+
+    /// Apply the curves on every pixel, 16 bits and float images keep their precision
+    pub fn apply(&self, img: DynamicImage) -> DynamicImage {
+        let [rgb, red, green, blue, alpha] = [
+            CurveChannel::RGB,
+            CurveChannel::RED,
+            CurveChannel::GREEN,
+            CurveChannel::BLUE,
+            CurveChannel::ALPHA,
+        ]
+        .map(|channel| self.spline(channel));
+        if [&rgb, &red, &green, &blue, &alpha]
+            .iter()
+            .all(|spline| spline.is_none())
+        {
+            trace!("No curve to apply");
+            return img;
+        }
+
+        let eval = |spline: &Option<MonotoneSpline>, v: f32| {
+            spline.as_ref().map_or(v, |spline| spline.eval(v))
+        };
+        let mut buffer = image_adjustments::to_working(&img, false);
+        for pixel in buffer.pixels_mut() {
+            for (c, spline) in [&red, &green, &blue].iter().enumerate() {
+                pixel[c] = eval(&rgb, eval(spline, pixel[c]));
+            }
+            pixel[3] = eval(&alpha, pixel[3]);
+        }
+        trace!("Curves applied");
+        image_adjustments::from_working(buffer, &img, false)
+    }
+}
+
+/// Fritsch-Carlson monotone cubic interpolation, on values from 0 to 1
+#[derive(Debug, Clone)]
+pub struct MonotoneSpline {
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+    tangents: Vec<f32>,
+}
+
+impl MonotoneSpline {
+    /// None without finite point, the points are clamped, sorted and the duplicated x ignored
+    //Deserialized points are not clamped by `CurvePoint::new`
+    pub fn new(points: &[CurvePoint]) -> Option<MonotoneSpline> {
+        let mut sorted: Vec<(f32, f32)> = points
+            .iter()
+            .filter(|point| point.x.is_finite() && point.y.is_finite())
+            .map(|point| {
+                (
+                    point.x.clamp(0.0, SCALE) / SCALE,
+                    point.y.clamp(0.0, SCALE) / SCALE,
+                )
+            })
+            .collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        sorted.dedup_by(|a, b| a.0 == b.0);
+        if sorted[0].0 > 0.0 {
+            sorted.insert(0, (0.0, 0.0));
+        }
+        if sorted[sorted.len() - 1].0 < 1.0 {
+            sorted.push((1.0, 1.0));
+        }
+        //At least 2 points now : one can't be at both edges
+        let (xs, ys): (Vec<f32>, Vec<f32>) = sorted.into_iter().unzip();
+
+        let n = xs.len();
+        let slopes: Vec<f32> = (0..n - 1)
+            .map(|k| (ys[k + 1] - ys[k]) / (xs[k + 1] - xs[k]))
+            .collect();
+        let mut tangents = vec![0.0; n];
+        tangents[0] = slopes[0];
+        tangents[n - 1] = slopes[n - 2];
+        for k in 1..n - 1 {
+            //A local extremum keeps a flat tangent
+            if slopes[k - 1] * slopes[k] > 0.0 {
+                tangents[k] = (slopes[k - 1] + slopes[k]) / 2.0;
+            }
+        }
+        for k in 0..n - 1 {
+            if slopes[k] == 0.0 {
+                tangents[k] = 0.0;
+                tangents[k + 1] = 0.0;
+                continue;
+            }
+            let a = tangents[k] / slopes[k];
+            let b = tangents[k + 1] / slopes[k];
+            let length = (a * a + b * b).sqrt();
+            //Limit the tangents so the segment stays monotone
+            if length > 3.0 {
+                tangents[k] = 3.0 / length * a * slopes[k];
+                tangents[k + 1] = 3.0 / length * b * slopes[k];
+            }
+        }
+        Some(MonotoneSpline { xs, ys, tangents })
+    }
+
+    /// Curve value at `x`, clamped between 0 and 1
+    pub fn eval(&self, x: f32) -> f32 {
+        let last = self.xs.len() - 1;
+        if x <= self.xs[0] {
+            return self.ys[0];
+        }
+        if x >= self.xs[last] {
+            return self.ys[last];
+        }
+        let k = self.xs.partition_point(|&xk| xk <= x) - 1;
+        let h = self.xs[k + 1] - self.xs[k];
+        let t = (x - self.xs[k]) / h;
+        let (t2, t3) = (t * t, t * t * t);
+        let y = (2.0 * t3 - 3.0 * t2 + 1.0) * self.ys[k]
+            + (t3 - 2.0 * t2 + t) * h * self.tangents[k]
+            + (-2.0 * t3 + 3.0 * t2) * self.ys[k + 1]
+            + (t3 - t2) * h * self.tangents[k + 1];
+        y.clamp(0.0, 1.0)
+    }
+}
+
+/// Remap the input range [input_black, input_white] to [output_black, output_white],
+/// with a gamma on the midtones (above 1 brightens them). Levels from 0 to 255
+//Can be instanciate from Typescript
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Levels {
+    pub input_black: f32,
+    pub input_white: f32,
+    pub gamma: f32,
+    pub output_black: f32,
+    pub output_white: f32,
+}
+
+#[wasm_bindgen]
+impl Levels {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        input_black: Option<f32>,
+        input_white: Option<f32>,
+        gamma: Option<f32>,
+        output_black: Option<f32>,
+        output_white: Option<f32>,
+    ) -> Levels {
+        let default = Levels::default();
+        Levels {
+            input_black: input_black.unwrap_or(default.input_black),
+            input_white: input_white.unwrap_or(default.input_white),
+            gamma: gamma.unwrap_or(default.gamma),
+            output_black: output_black.unwrap_or(default.output_black),
+            output_white: output_white.unwrap_or(default.output_white),
+        }
+    }
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self {
+            input_black: 0.0,
+            input_white: SCALE,
+            gamma: 1.0,
+            output_black: 0.0,
+            output_white: SCALE,
+        }
+    }
+}
+
+impl Levels {
+    pub fn is_identity(&self) -> bool {
+        *self == Levels::default()
+    }
+
+    /// Level of a value from 0 to 1
+    pub fn eval(&self, v: f32) -> f32 {
+        let black = self.input_black / SCALE;
+        //Avoid a division by 0 when both input points are equal
+        let range = (self.input_white / SCALE - black).max(1.0 / SCALE);
+        let v = ((v - black) / range).clamp(0.0, 1.0);
+        let v = v.powf(1.0 / self.gamma.max(0.01));
+        (self.output_black + v * (self.output_white - self.output_black)) / SCALE
+    }
+
+    /// Apply the levels on the RGB channels, alpha is kept
+    pub fn apply(&self, img: DynamicImage) -> DynamicImage {
+        if self.is_identity() {
+            trace!("No level to apply");
+            return img;
+        }
+        let mut buffer = image_adjustments::to_working(&img, false);
+        image_adjustments::map_pixels(&mut buffer, |rgb| rgb.map(|v| self.eval(v)));
+        trace!("Levels applied : {:?}", self);
+        image_adjustments::from_working(buffer, &img, false)
+    }
+}
@@ -1,8 +1,46 @@
-pub use image_processing::{ImageProcess, ImageParameters};
-pub use image_processing_result::{ImageProcessingResult, ImageDimension};
-pub use image_error::ErrorCode;
+pub use image_processing::{ImageProcess, ImageParameters, InputType};
+pub use image_processing_result::{ImageProcessingResult, ImageDimension, ImageSize};
+pub use image_output::{OutputFormat, OutputOptions};
+pub use image_pipeline::{Operation, Pipeline};
+pub use image_handle::ImageHandle;
+pub use image_history::History;
+pub use image_blend::BlendMode;
+pub use image_gradient::{Gradient, GradientStop, GradientType};
+pub use image_palette::ColorPalette;
+pub use image_resize::{Gravity, ResizeFilter, ResizeMode, ResizeOptions};
+pub use image_geometry::Interpolation;
+pub use image_metadata::{ExifInfo, ImageInfo};
+pub use image_embedded::{EmbeddedMetadata, MetadataOptions};
+pub use image_compression::{CompressionOptions, CompressionResult};
+pub use image_limits::DecodeLimits;
+pub use image_adjustments::Adjustment;
+pub use image_lut::{Lut3D, LutInterpolation};
+pub use image_tone::{CurveChannel, CurvePoint, Curves, Levels, MonotoneSpline};
+pub use image_animation::{Animation, AnimationFrame, ContactSheetOptions};
+pub use image_error::{ErrorCode, ErrorStage, ProcessingError};
+pub use image_input::ImageInput;
 
 mod image_processing;
 mod image_processing_result;
+mod image_output;
+mod image_pipeline;
+mod image_handle;
+mod image_history;
+mod image_blend;
+mod image_gradient;
+mod image_palette;
+mod image_resize;
+mod image_geometry;
+mod image_metadata;
+mod image_embedded;
+mod image_compression;
+mod image_animation;
+mod image_tone;
+mod image_lut;
+pub mod image_presets;
+pub mod image_adjustments;
 mod image_error;
+pub mod image_input;
+pub mod image_orientation;
+pub mod image_limits;
 pub mod image_filters;
\ No newline at end of file
@@ -1,7 +1,7 @@
-use crate::engine::{ImageProcessingResult, ImageDimension};
+use crate::engine::{ImageProcessingResult, ImageDimension, OutputOptions, Gradient, ColorPalette, BlendMode, ImageInput, CompressionOptions, CompressionResult, ResizeOptions, Interpolation, ImageInfo, ImageSize, DecodeLimits, ContactSheetOptions, Curves, Levels, Lut3D, LutInterpolation};
 use cfg_if::cfg_if;
 use engine::image_filters::GradientDirection;
-use engine::{image_filters::ColorRgba, ImageParameters, ImageProcess};
+use engine::{image_filters::ColorRgba, ImageParameters, ImageProcess, Pipeline};
 use log::*;
 use std::panic;
 use wasm_bindgen::prelude::*;
@@ -30,95 +30,413 @@ pub fn main() {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
 }
 
-/// Perform basic filter like Brighteness, huerotate, contrast etc
+/// Resize the image in the `width` * `height` box
+/// Without options the image fits in the box with the Lanczos3 filter
 #[wasm_bindgen]
 pub fn image_resize(
-    base64_input: String,
+    input: ImageInput,
     width: usize,
     height: usize,
-) -> Result<ImageProcessingResult, JsError> {
-    let image_processing = ImageProcess::new(base64_input)?;
+    options: Option<ResizeOptions>,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    let image_processing = ImageProcess::new(input)?;
     image_processing
-        .resize(width as u32, height as u32)
-        .map_err(|e| JsError::new(e.message()))
+        .resize(
+            width as u32,
+            height as u32,
+            options.unwrap_or_default(),
+            output.unwrap_or_default(),
+        )
+        .map_err(JsValue::from)
+}
+
+/// Keep the `width` * `height` area starting at (x, y)
+#[wasm_bindgen]
+pub fn image_crop(
+    input: ImageInput,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .crop(x, y, width, height, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+#[wasm_bindgen]
+pub fn image_rotate90(
+    input: ImageInput,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .rotate90(output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+#[wasm_bindgen]
+pub fn image_rotate180(
+    input: ImageInput,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .rotate180(output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+#[wasm_bindgen]
+pub fn image_rotate270(
+    input: ImageInput,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .rotate270(output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+#[wasm_bindgen]
+pub fn image_flip_horizontal(
+    input: ImageInput,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .flip_horizontal(output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+#[wasm_bindgen]
+pub fn image_flip_vertical(
+    input: ImageInput,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .flip_vertical(output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Rotate by `angle` degrees clockwise around the centre
+/// The uncovered area is filled with `fill` (transparent by default), `expand` grows the canvas
+#[wasm_bindgen]
+pub fn image_rotate(
+    input: ImageInput,
+    angle: f32,
+    interpolation: Option<Interpolation>,
+    fill: Option<ColorRgba>,
+    expand: Option<bool>,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .rotate(angle, interpolation, fill, expand, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Size in bytes of the encoded image (base64, data URL or bytes), the image is not decoded
+#[wasm_bindgen]
+pub fn image_weight(input: ImageInput) -> Result<usize, JsValue> {
+    input.encoded_length().map_err(JsValue::from)
 }
 
-/// Perform basic filter like Brighteness, huerotate, contrast etc
+/// Encoded size, decoded pixel buffer size and bits per pixel, only the header is read
 #[wasm_bindgen]
-pub fn image_weight(base64_input: String) -> Result<usize, JsError> {
-    ImageProcess::get_image_weight_byte(base64_input).map_err(|e| JsError::new(e.message()))
+pub fn image_size(input: ImageInput) -> Result<ImageSize, JsValue> {
+    ImageProcess::new(input)?.get_image_size().map_err(JsValue::from)
 }
 
+/// Format, dimensions, color type, bit depth, frames, ICC profile and EXIF fields
+/// Only the headers are read, the pixels are not decoded
 #[wasm_bindgen]
-pub fn image_dimension(base64_input: String) -> Result<ImageDimension, JsError> {
-    ImageProcess::new(base64_input)?.get_image_dimension().map_err(|e| JsError::new(e.message()))
+pub fn image_info(input: ImageInput) -> Result<ImageInfo, JsValue> {
+    ImageProcess::new(input)?.get_image_info().map_err(JsValue::from)
 }
 
+/// EXIF orientation of the image as stored, from 1 (upright) to 8
 #[wasm_bindgen]
-pub fn calc_best_size_ratio(base64_input: String, target_size: usize) -> Result<ImageProcessingResult, JsError> {
-    ImageProcess::calc_best_size_ratio(base64_input, target_size).map_err(|e| JsError::new(e.message()))
+pub fn image_orientation(input: ImageInput) -> Result<u32, JsValue> {
+    Ok(ImageProcess::new(input)?.get_orientation())
+}
+
+/// Rotate / flip the decoded images according to their EXIF orientation (enabled by default)
+/// Default of every call, `ImageHandle` can override it per image
+#[wasm_bindgen]
+pub fn set_auto_orient(enabled: bool) {
+    engine::image_orientation::set_auto_orient(enabled);
+}
+
+/// Limits checked before decoding any image (max width, height, pixels, allocation and frames)
+#[wasm_bindgen]
+pub fn set_decode_limits(limits: DecodeLimits) {
+    engine::image_limits::set_decode_limits(limits);
+}
+
+#[wasm_bindgen]
+pub fn get_decode_limits() -> DecodeLimits {
+    engine::image_limits::decode_limits()
+}
+
+#[wasm_bindgen]
+pub fn image_dimension(input: ImageInput) -> Result<ImageDimension, JsValue> {
+    ImageProcess::new(input)?.get_image_dimension().map_err(JsValue::from)
+}
+
+/// Number of frames of an animated GIF, APNG or WebP, 1 for a still image
+#[wasm_bindgen]
+pub fn image_frame_count(input: ImageInput) -> Result<u32, JsValue> {
+    ImageProcess::new(input)?.get_frame_count().map_err(JsValue::from)
+}
+
+/// Encode the frame at `index` as a still image
+#[wasm_bindgen]
+pub fn image_extract_frame(
+    input: ImageInput,
+    index: u32,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .extract_frame(index, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Every frame of the animation in a grid
+#[wasm_bindgen]
+pub fn image_contact_sheet(
+    input: ImageInput,
+    options: Option<ContactSheetOptions>,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .contact_sheet(options.unwrap_or_default(), output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+#[wasm_bindgen]
+pub fn calc_best_size_ratio(
+    input: ImageInput,
+    target_size: usize,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::calc_best_size_ratio(input, target_size, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Encode the image in at most `target_size` bytes
+/// Search the largest dimensions then the highest quality (JPEG only, WebP is lossless), error if unreachable
+#[wasm_bindgen]
+pub fn compress_to_size(
+    input: ImageInput,
+    target_size: usize,
+    options: Option<CompressionOptions>,
+    output: Option<OutputOptions>,
+) -> Result<CompressionResult, JsValue> {
+    ImageProcess::new(input)?
+        .compress_to_size(target_size, options.unwrap_or_default(), output.unwrap_or_default())
+        .map_err(JsValue::from)
 }
 
 #[wasm_bindgen]
 pub fn filter_params(
-    base64_input: String,
+    input: ImageInput,
     params: Option<ImageParameters>,
-) -> Result<ImageProcessingResult, JsError> {
-    let image_processing = ImageProcess::new(base64_input)?;
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    let image_processing = ImageProcess::new(input)?;
     image_processing
-        .compute_parameters(params.unwrap_or(ImageParameters::default()))
-        .map_err(|e| JsError::new(e.message()))
+        .compute_parameters(
+            params.unwrap_or_default(),
+            output.unwrap_or_default(),
+        )
+        .map_err(JsValue::from)
+}
+
+/// Perform a named preset filter (vintage, noir...), see `list_presets`
+/// `intensity` from 0 (original) to 1 (the default)
+#[wasm_bindgen]
+pub fn apply_preset(
+    input: ImageInput,
+    name: &str,
+    intensity: Option<f32>,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_preset(name, intensity, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Names accepted by `apply_preset`
+#[wasm_bindgen]
+pub fn list_presets() -> Vec<String> {
+    engine::image_presets::PRESET_NAMES
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Perform a color grading with a 3D LUT (`.cube` file or Hald CLUT)
+/// Tetrahedral interpolation by default, `intensity` from 0 (original) to 1 (the default)
+#[wasm_bindgen]
+pub fn filter_lut(
+    input: ImageInput,
+    lut: &Lut3D,
+    interpolation: Option<LutInterpolation>,
+    intensity: Option<f32>,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_lut(lut, interpolation, intensity, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Perform tone curves, see `Curves`
+#[wasm_bindgen]
+pub fn filter_curves(
+    input: ImageInput,
+    curves: &Curves,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_curves(curves, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Perform a levels adjustment, see `Levels`
+#[wasm_bindgen]
+pub fn filter_levels(
+    input: ImageInput,
+    levels: Levels,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_levels(levels, output.unwrap_or_default())
+        .map_err(JsValue::from)
 }
 
 /// Perform a Sobel filter
 #[wasm_bindgen]
-pub fn filter_sobel(base64_input: String) -> Result<ImageProcessingResult, JsError> {
-    ImageProcess::new(base64_input)?
-        .compute_filter_sobel()
-        .map_err(|e| JsError::new(e.message()))
+pub fn filter_sobel(
+    input: ImageInput,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_filter_sobel(output.unwrap_or_default())
+        .map_err(JsValue::from)
 }
 
 /// Perform a filter with colored band (vertical or horizontal)
+/// Without palette the default one is used, `softness` blends the bands edges (0 to 1)
 #[wasm_bindgen]
 pub fn filter_overlay_color(
-    base64_input: String,
+    input: ImageInput,
     direction: GradientDirection,
-) -> Result<ImageProcessingResult, JsError> {
-    ImageProcess::new(base64_input)?
+    palette: Option<ColorPalette>,
+    softness: Option<f32>,
+    blend_mode: Option<BlendMode>,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
         .compute_filter_band_color(
-            vec![
-                ColorRgba::new(214, 110, 250, 150),
-                ColorRgba::new(155, 100, 220, 150),
-                ColorRgba::new(150, 120, 240, 150),
-                ColorRgba::new(95, 105, 220, 150),
-                ColorRgba::new(110, 150, 250, 160),
-            ],
+            palette,
             direction,
+            softness,
+            blend_mode,
+            output.unwrap_or_default(),
         )
-        .map_err(|e| JsError::new(e.message()))
+        .map_err(JsValue::from)
 }
 
 /// Perform basic filter with lines of pixel drawn on the top of the image
 #[wasm_bindgen]
 pub fn filter_pixel(
-    base64_input: String,
+    input: ImageInput,
     pixel_type: engine::image_filters::FilterPixelType,
     color: ColorRgba,
-) -> Result<ImageProcessingResult, JsError> {
-    ImageProcess::new(base64_input)?
-        .compute_filter_pixel(pixel_type, color)
-        .map_err(|e| JsError::new(e.message()))
+    spacing: Option<u32>,
+    thickness: Option<u32>,
+    blend_mode: Option<BlendMode>,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_filter_pixel(
+            pixel_type,
+            color,
+            spacing,
+            thickness,
+            blend_mode,
+            output.unwrap_or_default(),
+        )
+        .map_err(JsValue::from)
 }
 
 /// Perform a vertical or horizontal linear gradient
 #[wasm_bindgen]
 pub fn filter_gradient(
-    base64_input: String,
+    input: ImageInput,
     direction: GradientDirection,
     from: ColorRgba,
     to: ColorRgba,
-) -> Result<ImageProcessingResult, JsError> {
-    ImageProcess::new(base64_input)?
-        .compute_filter_gradient(from, to, direction)
-        .map_err(|e| JsError::new(e.message()))
+    blend_mode: Option<BlendMode>,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_filter_gradient(from, to, direction, blend_mode, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Composite the layer image over the base image with a blend mode
+/// The layer is drawn at (x, y), (0, 0) by default, with an optional opacity (0 to 1)
+#[wasm_bindgen]
+pub fn composite_images(
+    input: ImageInput,
+    layer: ImageInput,
+    blend_mode: Option<BlendMode>,
+    x: Option<i32>,
+    y: Option<i32>,
+    opacity: Option<f32>,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_composite(
+            &ImageProcess::new(layer)?,
+            x.unwrap_or(0) as i64,
+            y.unwrap_or(0) as i64,
+            opacity.unwrap_or(1.0),
+            blend_mode.unwrap_or_default(),
+            output.unwrap_or_default(),
+        )
+        .map_err(JsValue::from)
+}
+
+
+/// Perform a linear / radial / conic gradient with several color stops
+#[wasm_bindgen]
+pub fn filter_custom_gradient(
+    input: ImageInput,
+    gradient: &Gradient,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_filter_custom_gradient(gradient, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Perform every operation of the pipeline with a single decode / encode
+#[wasm_bindgen]
+pub fn filter_pipeline(
+    input: ImageInput,
+    pipeline: &Pipeline,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    ImageProcess::new(input)?
+        .compute_pipeline(pipeline, output.unwrap_or_default())
+        .map_err(JsValue::from)
+}
+
+/// Perform a pipeline described in JSON, see `Pipeline::from_json`
+#[wasm_bindgen]
+pub fn filter_pipeline_json(
+    input: ImageInput,
+    json: &str,
+    output: Option<OutputOptions>,
+) -> Result<ImageProcessingResult, JsValue> {
+    filter_pipeline(input, &Pipeline::from_json(json)?, output)
 }
@@ -0,0 +1,8 @@
+use image::{DynamicImage, Rgb, RgbImage};
+
+/// Every pixel is different : red from left to right, green from top to bottom
+pub fn gradient(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        Rgb([(x * 240 / width) as u8, (y * 240 / height) as u8, 90])
+    }))
+}
@@ -0,0 +1,218 @@
+use image::{DynamicImage, GenericImageView, ImageBuffer, Rgb, RgbImage};
+use image_process::engine::image_adjustments;
+use image_process::engine::{Adjustment, ImageParameters};
+
+fn solid(rgb: [u8; 3]) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 2, Rgb(rgb)))
+}
+
+fn rgb(img: &DynamicImage) -> [u8; 3] {
+    let p = img.get_pixel(0, 0).0;
+    [p[0], p[1], p[2]]
+}
+
+#[test]
+fn exposure_is_in_stops() {
+    assert_eq!(
+        rgb(&image_adjustments::map_rgb(
+            solid([128; 3]),
+            image_adjustments::exposure(0.0, false)
+        )),
+        [128; 3]
+    );
+    //Mid gray is 0.216 in linear light, 0.432 once doubled
+    assert_eq!(
+        rgb(&image_adjustments::map_rgb(
+            solid([128; 3]),
+            image_adjustments::exposure(1.0, false)
+        )),
+        [176; 3]
+    );
+    assert_eq!(
+        rgb(&image_adjustments::map_rgb(
+            solid([128; 3]),
+            image_adjustments::exposure(-20.0, false)
+        )),
+        [0; 3]
+    );
+}
+
+#[test]
+fn white_balance_shifts_the_channels() {
+    let warm = rgb(&image_adjustments::map_rgb(
+        solid([128; 3]),
+        image_adjustments::white_balance(50.0, 0.0),
+    ));
+    assert!(warm[0] > 128 && warm[1] == 128 && warm[2] < 128);
+    let magenta = rgb(&image_adjustments::map_rgb(
+        solid([128; 3]),
+        image_adjustments::white_balance(0.0, 50.0),
+    ));
+    assert!(magenta[0] > 128 && magenta[1] < 128 && magenta[2] > 128);
+}
+
+#[test]
+fn saturation_and_vibrance() {
+    let gray = rgb(&image_adjustments::map_rgb(
+        solid([200, 100, 50]),
+        image_adjustments::saturation(-100.0),
+    ));
+    assert!(gray[0] == gray[1] && gray[1] == gray[2]);
+
+    let spread = |c: [u8; 3]| *c.iter().max().unwrap() as i32 - *c.iter().min().unwrap() as i32;
+    let dull = solid([140, 120, 110]);
+    let vivid = solid([230, 40, 20]);
+    let dull_gain = spread(rgb(&image_adjustments::map_rgb(
+        dull.clone(),
+        image_adjustments::vibrance(50.0),
+    ))) - spread(rgb(&dull));
+    let vivid_gain = spread(rgb(&image_adjustments::map_rgb(
+        vivid.clone(),
+        image_adjustments::vibrance(50.0),
+    ))) - spread(rgb(&vivid));
+    assert!(dull_gain as f32 / 30.0 > vivid_gain as f32 / 210.0);
+}
+
+#[test]
+fn gamma_highlights_and_shadows() {
+    assert_eq!(
+        rgb(&image_adjustments::map_rgb(
+            solid([90; 3]),
+            image_adjustments::gamma(1.0)
+        )),
+        [90; 3]
+    );
+    assert!(
+        rgb(&image_adjustments::map_rgb(
+            solid([90; 3]),
+            image_adjustments::gamma(2.0)
+        ))[0]
+            > 90
+    );
+
+    let bright = rgb(&image_adjustments::map_rgb(
+        solid([230; 3]),
+        image_adjustments::highlights_shadows(-100.0, 0.0),
+    ))[0];
+    let dark = rgb(&image_adjustments::map_rgb(
+        solid([30; 3]),
+        image_adjustments::highlights_shadows(-100.0, 0.0),
+    ))[0];
+    assert!(230 - bright > 30 - dark);
+    let lifted = rgb(&image_adjustments::map_rgb(
+        solid([30; 3]),
+        image_adjustments::highlights_shadows(0.0, 100.0),
+    ))[0];
+    assert!(lifted > 60);
+}
+
+#[test]
+fn sharpen_increases_the_edge_contrast() {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_fn(8, 8, |x, _| {
+        Rgb(if x < 4 { [100; 3] } else { [150; 3] })
+    }));
+    let sharpened =
+        DynamicImage::ImageRgba32F(image_adjustments::sharpen(&img.to_rgba32f(), 1.0)).to_rgba8();
+    assert!(sharpened.get_pixel(3, 4)[0] < 100);
+    assert!(sharpened.get_pixel(4, 4)[0] > 150);
+    assert_eq!(sharpened.get_pixel(0, 4)[0], 100);
+}
+
+#[test]
+fn precision_is_kept() {
+    let img = DynamicImage::ImageRgb16(ImageBuffer::from_pixel(2, 2, Rgb([1000u16, 2000, 3000])));
+    let adjusted = image_adjustments::map_rgb(img, image_adjustments::saturation(0.0));
+    assert!(matches!(adjusted, DynamicImage::ImageRgba16(_)));
+    assert_eq!(adjusted.to_rgb16().get_pixel(0, 0).0, [1000, 2000, 3000]);
+}
+
+#[test]
+fn parameters_in_order() {
+    let mut params = ImageParameters::new();
+    params.saturation = Some(-100.0);
+    params.temperature = Some(100.0);
+    //The white balance runs first, then the saturation removes the tint
+    let img = params.apply_filter(solid([128; 3]));
+    let [r, g, b] = rgb(&img);
+    assert!(r == g && g == b);
+    assert_eq!(img.dimensions(), (2, 2));
+}
+
+#[test]
+fn identity_adjustments_are_skipped() {
+    //A blur of 0 used to run a full gaussian pass
+    let checker = DynamicImage::ImageRgb8(RgbImage::from_fn(8, 8, |x, y| {
+        Rgb(if (x + y) % 2 == 0 { [0; 3] } else { [255; 3] })
+    }));
+    let img = ImageParameters::new().apply_filter(checker.clone());
+    assert!(matches!(img, DynamicImage::ImageRgb8(_)));
+    assert_eq!(img.as_bytes(), checker.as_bytes());
+
+    let mut params = ImageParameters::new();
+    params.hue = Some(360);
+    params.gamma = Some(1.0);
+    params.sharpen = Some(0.0);
+    assert!(params.is_identity(Adjustment::HUE));
+    assert!(params.is_identity(Adjustment::GAMMA));
+    assert_eq!(
+        params.apply_filter(checker.clone()).as_bytes(),
+        checker.as_bytes()
+    );
+}
+
+#[test]
+fn custom_order() {
+    let mut params = ImageParameters::new();
+    params.brighten = Some(50);
+    params.invert = Some(true);
+    //Brighten then invert by default
+    assert_eq!(rgb(&params.apply_filter(solid([100; 3]))), [105; 3]);
+
+    params.order = Some(vec![Adjustment::INVERT, Adjustment::INVERT]);
+    assert_eq!(rgb(&params.apply_filter(solid([100; 3]))), [205; 3]);
+    let order = params.get_order();
+    assert_eq!(order.len(), Adjustment::DEFAULT_ORDER.len());
+    assert_eq!(order[0], "INVERT");
+    assert_eq!(order[1], "EXPOSURE");
+
+    assert_eq!(
+        Adjustment::from_name("white_balance"),
+        Some(Adjustment::WHITE_BALANCE)
+    );
+    assert_eq!(Adjustment::from_name("sepia"), None);
+}
+
+#[test]
+fn linear_light() {
+    let mut params = ImageParameters::new();
+    params.saturation = Some(-100.0);
+    //Pure red has a luma of 0.2126, in sRGB or in linear light
+    assert_eq!(rgb(&params.apply_filter(solid([255, 0, 0]))), [54; 3]);
+    params.linear_light = Some(true);
+    assert_eq!(rgb(&params.apply_filter(solid([255, 0, 0]))), [127; 3]);
+
+    //The round trip through linear light keeps the values
+    params.saturation = Some(0.0);
+    params.brighten = Some(0);
+    params.exposure = Some(0.0);
+    params.sharpen = Some(1.0);
+    let flat = solid([37, 128, 201]);
+    assert_eq!(rgb(&params.apply_filter(flat)), [37, 128, 201]);
+}
+
+#[test]
+fn existing_parameters_keep_their_order() {
+    let mut params = ImageParameters::new();
+    params.brighten = Some(40);
+    params.hue = Some(90);
+    params.constrast = Some(30.0);
+    params.invert = Some(true);
+    let img = solid([120, 60, 200]);
+    //Same chain as the image crate filters : brighten, hue, contrast then invert
+    let mut expected = img.brighten(40).huerotate(90).adjust_contrast(30.0);
+    expected.invert();
+    let adjusted = rgb(&params.apply_filter(img));
+    for (a, b) in adjusted.iter().zip(rgb(&expected).iter()) {
+        assert!((*a as i32 - *b as i32).abs() <= 2, "{:?}", adjusted);
+    }
+}
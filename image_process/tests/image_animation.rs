@@ -0,0 +1,326 @@
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, GenericImageView, ImageFormat, RgbaImage};
+use image_process::engine::{
+    Animation, ContactSheetOptions, DecodeLimits, ErrorCode, ImageProcess, Operation, OutputFormat,
+    OutputOptions,
+};
+
+const COLORS: [[u8; 4]; 3] = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+
+//4x2 frames : left half colored, right half white
+fn gif_bytes() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut bytes);
+        encoder.set_repeat(Repeat::Infinite).unwrap();
+        for (i, color) in COLORS.iter().enumerate() {
+            let frame = RgbaImage::from_fn(4, 2, |x, _| {
+                image::Rgba(if x < 2 { *color } else { [255; 4] })
+            });
+            let delay = Delay::from_numer_denom_ms(50 * (i as u32 + 1), 1);
+            encoder
+                .encode_frame(Frame::from_parts(frame, 0, 0, delay))
+                .unwrap();
+        }
+    }
+    bytes
+}
+
+//Lossless bitstream of a solid color : every prefix code has a single symbol, so no pixel data
+fn vp8l_solid(width: u32, height: u32, [r, g, b, a]: [u8; 4]) -> Vec<u8> {
+    let mut bits: Vec<(u32, u32)> = vec![
+        (0x2f, 8),
+        (width - 1, 14),
+        (height - 1, 14),
+        (1, 1),
+        (0, 3),
+        (0, 1),
+        (0, 1),
+        (0, 1),
+    ];
+    for symbol in [g, r, b, a, 0] {
+        bits.extend([(1, 1), (0, 1), (1, 1), (symbol as u32, 8)]);
+    }
+    let mut bytes = Vec::new();
+    let (mut acc, mut count) = (0u64, 0);
+    for (value, size) in bits {
+        acc |= (value as u64) << count;
+        count += size;
+        while count >= 8 {
+            bytes.push(acc as u8);
+            acc >>= 8;
+            count -= 8;
+        }
+    }
+    if count > 0 {
+        bytes.push(acc as u8);
+    }
+    bytes
+}
+
+fn chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut bytes = kind.to_vec();
+    bytes.extend((data.len() as u32).to_le_bytes());
+    bytes.extend(data);
+    if data.len() % 2 == 1 {
+        bytes.push(0);
+    }
+    bytes
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let b = value.to_le_bytes();
+    [b[0], b[1], b[2]]
+}
+
+//4x4 animation : red canvas, then a blue 2x2 square at (2, 2) disposed after its frame
+fn webp_bytes() -> Vec<u8> {
+    let frame = |x: u32, y: u32, w: u32, h: u32, flags: u8, color: [u8; 4]| {
+        let mut data = Vec::new();
+        for value in [x / 2, y / 2, w - 1, h - 1, 80] {
+            data.extend(u24(value));
+        }
+        data.push(flags);
+        data.extend(chunk(b"VP8L", &vp8l_solid(w, h, color)));
+        chunk(b"ANMF", &data)
+    };
+    let mut vp8x = vec![0x12, 0, 0, 0];
+    vp8x.extend(u24(3));
+    vp8x.extend(u24(3));
+    let mut body = b"WEBP".to_vec();
+    body.extend(chunk(b"VP8X", &vp8x));
+    body.extend(chunk(b"ANIM", &[0, 0, 0, 0, 2, 0]));
+    body.extend(frame(0, 0, 4, 4, 0, [255, 0, 0, 255]));
+    body.extend(frame(2, 2, 2, 2, 1, [0, 0, 255, 255]));
+    body.extend(frame(0, 0, 2, 2, 0, [0, 255, 0, 128]));
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend((body.len() as u32).to_le_bytes());
+    bytes.extend(body);
+    bytes
+}
+
+//ALPH and VP8 chunks of a 100x100 lossy WebP with a lossless compressed alpha
+fn lossy_alpha_chunks() -> (Vec<u8>, Vec<u8>) {
+    let path = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "images",
+        "lossy_alpha.webp",
+    ];
+    let bytes = std::fs::read(path.iter().collect::<std::path::PathBuf>()).unwrap();
+    let (mut alph, mut vp8) = (Vec::new(), Vec::new());
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let b = &bytes[at + 4..at + 8];
+        let length = u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize;
+        let data = bytes[at + 8..at + 8 + length].to_vec();
+        match &bytes[at..at + 4] {
+            b"ALPH" => alph = data,
+            b"VP8 " => vp8 = data,
+            _ => {}
+        }
+        at += 8 + length + (length & 1);
+    }
+    (alph, vp8)
+}
+
+//Animation of a single 100x100 lossy frame with its alpha
+fn lossy_webp(alph: &[u8], vp8: &[u8]) -> Vec<u8> {
+    let mut anmf = Vec::new();
+    for value in [0, 0, 99, 99, 100] {
+        anmf.extend(u24(value));
+    }
+    anmf.push(0x02);
+    anmf.extend(chunk(b"ALPH", alph));
+    anmf.extend(chunk(b"VP8 ", vp8));
+    let mut vp8x = vec![0x12, 0, 0, 0];
+    vp8x.extend(u24(99));
+    vp8x.extend(u24(99));
+    let mut body = b"WEBP".to_vec();
+    body.extend(chunk(b"VP8X", &vp8x));
+    body.extend(chunk(b"ANIM", &[0; 6]));
+    body.extend(chunk(b"ANMF", &anmf));
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend((body.len() as u32).to_le_bytes());
+    bytes.extend(body);
+    bytes
+}
+
+//Filter the alpha plane like an encoder : difference with the left, above or gradient prediction
+fn filter_alpha(plane: &[u8], width: usize, method: u8) -> Vec<u8> {
+    (0..plane.len())
+        .map(|at| {
+            let (x, y) = (at % width, at / width);
+            let predictor = match (x, y, method) {
+                (_, _, 0) | (0, 0, _) => 0,
+                (0, _, _) => plane[at - width],
+                (_, 0, _) | (_, _, 1) => plane[at - 1],
+                (_, _, 2) => plane[at - width],
+                _ => (plane[at - 1] as i16 + plane[at - width] as i16
+                    - plane[at - width - 1] as i16)
+                    .clamp(0, 255) as u8,
+            };
+            plane[at].wrapping_sub(predictor)
+        })
+        .collect()
+}
+
+#[test]
+fn gif_frames_and_delays() {
+    let process = ImageProcess::new(gif_bytes()).unwrap();
+    assert_eq!(process.get_frame_count().unwrap(), 3);
+
+    let animation = process.get_animation().unwrap().unwrap();
+    assert_eq!(animation.plays(), 0);
+    let delays: Vec<u32> = animation.frames().iter().map(|f| f.delay_ms).collect();
+    assert_eq!(delays, vec![50, 100, 150]);
+    for (frame, color) in animation.frames().iter().zip(COLORS.iter()) {
+        assert_eq!(frame.image.get_pixel(0, 0).0, *color);
+    }
+}
+
+#[test]
+fn filters_are_applied_on_every_frame() {
+    let process = ImageProcess::new(gif_bytes()).unwrap();
+
+    for format in [OutputFormat::GIF, OutputFormat::PNG] {
+        let result = process
+            .compute_operation(&Operation::FlipHorizontal, OutputOptions::new(format, None))
+            .unwrap();
+        let animation = ImageProcess::new(result.to_byte())
+            .unwrap()
+            .get_animation()
+            .unwrap()
+            .expect("the output should be animated");
+        assert_eq!(animation.frame_count(), 3);
+        assert_eq!(animation.frames()[1].delay_ms, 100);
+        for (frame, color) in animation.frames().iter().zip(COLORS.iter()) {
+            assert_eq!(frame.image.get_pixel(0, 0).0, [255; 4]);
+            assert_eq!(frame.image.get_pixel(3, 0).0, *color);
+        }
+    }
+
+    let mut still = OutputOptions::new(OutputFormat::PNG, None);
+    still.keep_animation = false;
+    let result = process
+        .compute_operation(&Operation::FlipHorizontal, still)
+        .unwrap();
+    assert_eq!(
+        ImageProcess::new(result.to_byte())
+            .unwrap()
+            .get_frame_count()
+            .unwrap(),
+        1
+    );
+}
+
+#[test]
+fn extract_frame_and_contact_sheet() {
+    let process = ImageProcess::new(gif_bytes()).unwrap();
+
+    let frame = process.extract_frame(2, OutputOptions::default()).unwrap();
+    let frame = image::load_from_memory(&frame.to_byte()).unwrap();
+    assert_eq!(frame.get_pixel(0, 0).0, COLORS[2]);
+
+    let error = process
+        .extract_frame(3, OutputOptions::default())
+        .expect_err("there are only 3 frames");
+    assert!(error == ErrorCode::FrameNotFound);
+    assert_eq!(error.detail("frame_count"), Some("3"));
+
+    let sheet = process
+        .contact_sheet(ContactSheetOptions::default(), OutputOptions::default())
+        .unwrap();
+    let sheet = image::load_from_memory(&sheet.to_byte()).unwrap();
+    //2 columns and 2 rows of 4x2 frames, 4 pixels around them
+    assert_eq!(sheet.dimensions(), (2 * 4 + 3 * 4, 2 * 2 + 3 * 4));
+    assert_eq!(sheet.get_pixel(4, 4).0, COLORS[0]);
+    assert_eq!(sheet.get_pixel(12, 4).0, COLORS[1]);
+    assert_eq!(sheet.get_pixel(4, 10).0, COLORS[2]);
+}
+
+#[test]
+fn webp_frames_are_composited() {
+    let process = ImageProcess::new(webp_bytes()).unwrap();
+    assert_eq!(process.get_frame_count().unwrap(), 3);
+    assert_eq!(process.get_dynamic_image().unwrap().dimensions(), (4, 4));
+
+    let animation = process.get_animation().unwrap().unwrap();
+    assert_eq!(animation.plays(), 2);
+    let frames = animation.frames();
+    assert_eq!(frames[0].image.get_pixel(3, 3).0, [255, 0, 0, 255]);
+    assert_eq!(frames[1].image.get_pixel(3, 3).0, [0, 0, 255, 255]);
+    assert_eq!(frames[1].image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    //The blue square is disposed, the green one is blended on the red canvas
+    assert_eq!(frames[2].image.get_pixel(3, 3).0, [0, 0, 0, 0]);
+    let blended = frames[2].image.get_pixel(0, 0).0;
+    assert!(blended[0] > 100 && blended[1] > 100 && blended[3] >= 254);
+    assert_eq!(frames[2].delay_ms, 80);
+}
+
+#[test]
+fn contact_sheet_is_checked_against_the_limits() {
+    let process = ImageProcess::new(gif_bytes()).unwrap();
+
+    let options = ContactSheetOptions::new(None, None, Some(u32::MAX), None);
+    let error = process
+        .contact_sheet(options, OutputOptions::default())
+        .expect_err("the spacing alone is too large");
+    assert!(error == ErrorCode::LimitExceeded);
+    assert_eq!(error.detail("limit"), Some("width"));
+
+    let limited = ImageProcess::new(gif_bytes())
+        .unwrap()
+        .with_limits(DecodeLimits::new(None, None, Some(200), None, None));
+    let error = limited
+        .contact_sheet(ContactSheetOptions::default(), OutputOptions::default())
+        .expect_err("20x16 sheet for 200 pixels");
+    assert_eq!(error.detail("limit"), Some("pixels"));
+    assert_eq!(error.detail("requested"), Some("320"));
+
+    //Without limit, the sheet still has to fit in an image
+    let animation = process.get_animation().unwrap().unwrap();
+    let options = ContactSheetOptions::new(None, Some(u32::MAX), None, None);
+    let error = animation
+        .contact_sheet(&options, &DecodeLimits::unlimited())
+        .expect_err("larger than an u32");
+    assert!(error == ErrorCode::LimitExceeded);
+    assert_eq!(error.detail("max"), Some(u32::MAX.to_string().as_str()));
+}
+
+#[test]
+fn lossy_webp_frames_keep_their_alpha() {
+    let (alph, vp8) = lossy_alpha_chunks();
+    let limits = DecodeLimits::default();
+    let animation =
+        Animation::decode(ImageFormat::WebP, &lossy_webp(&alph, &vp8), &limits).unwrap();
+    let frame = animation.frames()[0].image.to_rgba8();
+    assert_eq!(frame.dimensions(), (100, 100));
+    //Same alpha as the reference decoder
+    let alpha: Vec<u8> = frame.pixels().map(|p| p[3]).collect();
+    assert_eq!(alpha.iter().map(|&a| a as u64).sum::<u64>(), 953_271);
+    assert_eq!(frame.get_pixel(0, 0)[3], 0);
+    assert_eq!(frame.get_pixel(50, 30)[3], 255);
+
+    //Raw alpha, with each filter
+    let plane: Vec<u8> = (0..100 * 100)
+        .map(|at| ((at % 100) * 7 + (at / 100) * 3) as u8)
+        .collect();
+    for method in 0..4 {
+        let mut raw = vec![method << 2];
+        raw.extend(filter_alpha(&plane, 100, method));
+        let animation =
+            Animation::decode(ImageFormat::WebP, &lossy_webp(&raw, &vp8), &limits).unwrap();
+        let alpha: Vec<u8> = animation.frames()[0]
+            .image
+            .to_rgba8()
+            .pixels()
+            .map(|p| p[3])
+            .collect();
+        assert_eq!(alpha, plane, "filter {}", method);
+    }
+
+    let error = Animation::decode(ImageFormat::WebP, &lossy_webp(&[0, 1, 2], &vp8), &limits)
+        .expect_err("the raw alpha is truncated");
+    assert!(error == ErrorCode::UnableToDecode);
+}
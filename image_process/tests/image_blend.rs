@@ -0,0 +1,116 @@
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use image_process::engine::image_filters::{self, GradientDirection};
+use image_process::engine::{BlendMode, ImageProcess, OutputOptions};
+
+const BACKDROP: Rgba<u8> = Rgba([200, 100, 50, 255]);
+const SOURCE: Rgba<u8> = Rgba([100, 150, 250, 255]);
+
+#[test]
+fn blend_separable_modes() {
+    assert_eq!(BlendMode::NORMAL.blend_pixel(BACKDROP, SOURCE), SOURCE);
+    assert_eq!(
+        BlendMode::MULTIPLY.blend_pixel(BACKDROP, SOURCE),
+        Rgba([78, 59, 49, 255])
+    );
+    assert_eq!(
+        BlendMode::SCREEN.blend_pixel(BACKDROP, SOURCE),
+        Rgba([222, 191, 251, 255])
+    );
+    assert_eq!(
+        BlendMode::DARKEN.blend_pixel(BACKDROP, SOURCE),
+        Rgba([100, 100, 50, 255])
+    );
+    assert_eq!(
+        BlendMode::LIGHTEN.blend_pixel(BACKDROP, SOURCE),
+        Rgba([200, 150, 250, 255])
+    );
+    assert_eq!(
+        BlendMode::DIFFERENCE.blend_pixel(BACKDROP, SOURCE),
+        Rgba([100, 50, 200, 255])
+    );
+}
+
+#[test]
+fn blend_transparent_source_keeps_backdrop() {
+    let transparent = Rgba([10, 20, 30, 0]);
+    for mode in [
+        BlendMode::OVERLAY,
+        BlendMode::SOFT_LIGHT,
+        BlendMode::COLOR_DODGE,
+        BlendMode::COLOR_BURN,
+        BlendMode::EXCLUSION,
+        BlendMode::HUE,
+        BlendMode::LUMINOSITY,
+    ] {
+        assert_eq!(
+            mode.blend_pixel(BACKDROP, transparent),
+            BACKDROP,
+            "{:?}",
+            mode
+        );
+    }
+}
+
+#[test]
+fn blend_luminosity_and_color_are_complementary() {
+    let luma = |p: Rgba<u8>| 0.3 * p[0] as f32 + 0.59 * p[1] as f32 + 0.11 * p[2] as f32;
+
+    //COLOR keeps the luminosity of the backdrop, LUMINOSITY the one of the source
+    let color = BlendMode::COLOR.blend_pixel(BACKDROP, SOURCE);
+    assert!((luma(color) - luma(BACKDROP)).abs() < 1.5);
+    let luminosity = BlendMode::LUMINOSITY.blend_pixel(BACKDROP, SOURCE);
+    assert!((luma(luminosity) - luma(SOURCE)).abs() < 1.5);
+}
+
+#[test]
+fn blend_mode_on_overlay_filters() {
+    let mut img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 8, BACKDROP));
+    let filtered = image_filters::filter_band_color(
+        &mut img,
+        vec![SOURCE],
+        GradientDirection::VERTICAL,
+        0.0,
+        BlendMode::MULTIPLY,
+    )
+    .unwrap();
+    assert_eq!(filtered.get_pixel(3, 3), Rgba([78, 59, 49, 255]));
+}
+
+#[test]
+fn composite_layer_with_offset_and_opacity() {
+    let encode = |img: RgbaImage| {
+        OutputOptions::default()
+            .encode(&DynamicImage::ImageRgba8(img))
+            .unwrap()
+    };
+    let base = ImageProcess::new(encode(RgbaImage::from_pixel(6, 6, BACKDROP))).unwrap();
+    let layer = ImageProcess::new(encode(RgbaImage::from_pixel(4, 4, SOURCE))).unwrap();
+
+    let result = base
+        .compute_composite(
+            &layer,
+            4,
+            -2,
+            1.0,
+            BlendMode::DIFFERENCE,
+            OutputOptions::default(),
+        )
+        .unwrap();
+    let composite = image::load_from_memory(&result.to_byte()).unwrap();
+    assert_eq!(composite.get_pixel(0, 0), BACKDROP);
+    assert_eq!(composite.get_pixel(5, 1), Rgba([100, 50, 200, 255]));
+    assert_eq!(composite.get_pixel(5, 2), BACKDROP);
+
+    let result = base
+        .compute_composite(
+            &layer,
+            0,
+            0,
+            0.0,
+            BlendMode::MULTIPLY,
+            OutputOptions::default(),
+        )
+        .unwrap();
+    let composite = image::load_from_memory(&result.to_byte()).unwrap();
+    assert_eq!(composite.get_pixel(1, 1), BACKDROP);
+}
@@ -0,0 +1,48 @@
+use image::{DynamicImage, RgbImage};
+use image_process::engine::{
+    CompressionOptions, ErrorCode, ImageProcess, OutputFormat, OutputOptions,
+};
+
+fn noisy_image(w: u32, h: u32) -> ImageProcess {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_fn(w, h, |x, y| {
+        let v = x.wrapping_mul(7919) ^ y.wrapping_mul(104729);
+        image::Rgb([(v % 251) as u8, (v / 7 % 253) as u8, (v / 13 % 255) as u8])
+    }));
+    ImageProcess::new(OutputOptions::default().encode(&img).unwrap()).unwrap()
+}
+
+#[test]
+fn jpeg_fits_the_target_size() {
+    let output = OutputOptions::new(OutputFormat::JPEG, None);
+    let compressed = noisy_image(200, 120)
+        .compress_to_size(8_000, CompressionOptions::default(), output)
+        .unwrap();
+
+    assert!(compressed.byte_length() <= 8_000);
+    assert!(compressed.get_quality().is_some());
+    assert!(compressed.get_width() <= 200 && compressed.get_height() <= 120);
+    assert!(compressed.get_height() >= CompressionOptions::default().min_dimension);
+}
+
+#[test]
+fn lossless_fits_by_scaling_only() {
+    for format in [OutputFormat::PNG, OutputFormat::WEBP] {
+        let output = OutputOptions::new(format, None);
+        let compressed = noisy_image(100, 100)
+            .compress_to_size(10_000, CompressionOptions::default(), output)
+            .unwrap();
+
+        assert!(compressed.byte_length() <= 10_000);
+        assert!(compressed.get_quality().is_none());
+        assert!(compressed.get_width() < 100);
+    }
+}
+
+#[test]
+fn unreachable_target_is_an_error() {
+    let output = OutputOptions::new(OutputFormat::PNG, None);
+    let result = noisy_image(4, 4).compress_to_size(10, CompressionOptions::default(), output);
+    let error = result.expect_err("target size should be unreachable");
+    assert!(error == ErrorCode::TargetSizeUnreachable);
+    assert_eq!(error.detail("target_size"), Some("10"));
+}
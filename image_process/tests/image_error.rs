@@ -0,0 +1,65 @@
+use image::{DynamicImage, RgbImage};
+use image_process::engine::{
+    ErrorCode, ErrorStage, ImageProcess, OutputFormat, OutputOptions, Pipeline,
+};
+
+fn png_bytes() -> Vec<u8> {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(16, 16, image::Rgb([10, 20, 30])));
+    OutputOptions::new(OutputFormat::PNG, None)
+        .encode(&img)
+        .unwrap()
+}
+
+#[test]
+fn truncated_input_has_decode_context() {
+    let mut bytes = png_bytes();
+    bytes.truncate(bytes.len() / 2);
+    let error = ImageProcess::new(bytes)
+        .unwrap()
+        .get_dynamic_image()
+        .expect_err("truncated image should not decode");
+
+    assert!(error == ErrorCode::UnableToDecode);
+    assert_eq!(error.code().code(), "UNABLE_TO_DECODE");
+    assert_eq!(error.stage(), ErrorStage::DECODE);
+    assert_eq!(error.detail("format"), Some("Png"));
+    assert!(error.cause().is_some());
+    assert!(error
+        .message()
+        .starts_with(ErrorCode::UnableToDecode.message()));
+}
+
+#[test]
+fn invalid_base64_is_an_input_error() {
+    let error =
+        ImageProcess::new("not base64 !".to_string()).expect_err("invalid base64 should fail");
+
+    assert!(error == ErrorCode::UnableToDecode);
+    assert_eq!(error.stage(), ErrorStage::INPUT);
+    assert!(error.cause().is_some());
+}
+
+#[test]
+fn encoding_error_names_the_format() {
+    //GIF frames are limited to 65535 pixels wide
+    let img = DynamicImage::ImageRgb8(RgbImage::new(70_000, 1));
+    let error = OutputOptions::new(OutputFormat::GIF, None)
+        .encode(&img)
+        .expect_err("too wide for a GIF");
+
+    assert!(error == ErrorCode::UnableToSave);
+    assert_eq!(error.stage(), ErrorStage::ENCODE);
+    assert_eq!(error.detail("format"), Some("GIF"));
+    assert!(error.cause().is_some());
+}
+
+#[test]
+fn pipeline_parsing_error_has_its_position() {
+    let error =
+        Pipeline::parse_operations("[\n  {\"op\": }\n]").expect_err("invalid JSON should fail");
+
+    assert!(error == ErrorCode::InvalidParsing);
+    assert_eq!(error.stage(), ErrorStage::INPUT);
+    assert_eq!(error.detail("line"), Some("2"));
+    assert!(error.detail("column").is_some());
+}
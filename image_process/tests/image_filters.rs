@@ -0,0 +1,175 @@
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use image_process::engine::image_filters::GradientDirection;
+use image_process::engine::image_filters::{self, ColorRgba, FilterPixelType};
+use image_process::engine::{BlendMode, ColorPalette, Gradient, GradientType};
+
+const PIXEL_TYPES: [FilterPixelType; 4] = [
+    FilterPixelType::VERTICAL,
+    FilterPixelType::HORIZONTAL,
+    FilterPixelType::DIAGONAL,
+    FilterPixelType::CIRCLE,
+];
+
+/// Pixels colored by the pixel filter on a white image
+fn pixel_filter_mask(pixel_type: FilterPixelType, spacing: u32, thickness: u32) -> Vec<bool> {
+    let mut img =
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(32, 24, Rgba([255, 255, 255, 255])));
+    let filtered = image_filters::filter_pixel(
+        &mut img,
+        pixel_type,
+        ColorRgba::new(0, 0, 0, 255),
+        spacing,
+        thickness,
+        BlendMode::NORMAL,
+    )
+    .unwrap();
+
+    filtered
+        .pixels()
+        .map(|(_, _, pixel)| pixel == Rgba([0, 0, 0, 255]))
+        .collect()
+}
+
+#[test]
+fn pixel_types_produce_different_masks() {
+    let masks: Vec<Vec<bool>> = PIXEL_TYPES
+        .iter()
+        .map(|pixel_type| pixel_filter_mask(*pixel_type, 4, 1))
+        .collect();
+
+    for (i, mask) in masks.iter().enumerate() {
+        assert!(mask.contains(&true), "{:?} draws nothing", PIXEL_TYPES[i]);
+        assert!(
+            mask.contains(&false),
+            "{:?} fills the image",
+            PIXEL_TYPES[i]
+        );
+        for (j, other) in masks.iter().enumerate().skip(i + 1) {
+            assert_ne!(
+                mask, other,
+                "{:?} and {:?} are identical",
+                PIXEL_TYPES[i], PIXEL_TYPES[j]
+            );
+        }
+    }
+}
+
+#[test]
+fn pixel_vertical_keeps_default_pattern() {
+    let mask = pixel_filter_mask(
+        FilterPixelType::VERTICAL,
+        image_filters::DEFAULT_PIXEL_SPACING,
+        image_filters::DEFAULT_PIXEL_THICKNESS,
+    );
+    for (i, colored) in mask.iter().enumerate() {
+        assert_eq!(*colored, i % 32 % 4 == 0);
+    }
+}
+
+#[test]
+fn pixel_spacing_and_thickness() {
+    let mask = pixel_filter_mask(FilterPixelType::HORIZONTAL, 6, 2);
+    for (i, colored) in mask.iter().enumerate() {
+        assert_eq!(*colored, (i / 32) % 6 < 2);
+    }
+}
+
+fn custom_gradient_layer(gradient: &Gradient) -> RgbaImage {
+    let mut img = DynamicImage::ImageRgba8(RgbaImage::new(41, 21));
+    image_filters::filter_custom_gradient(&mut img, gradient)
+        .unwrap()
+        .to_rgba8()
+}
+
+#[test]
+fn custom_gradient_linear_angle_and_stops() {
+    let mut gradient = Gradient::new(GradientType::LINEAR);
+    gradient.add_stop(0.0, ColorRgba::new(0, 0, 0, 255));
+    gradient.add_stop(0.5, ColorRgba::new(255, 0, 0, 255));
+    gradient.add_stop(1.0, ColorRgba::new(255, 255, 255, 255));
+
+    let layer = custom_gradient_layer(&gradient);
+    assert!(layer.get_pixel(0, 10)[0] < 10);
+    assert_eq!(*layer.get_pixel(20, 10), Rgba([255, 0, 0, 255]));
+    assert!(layer.get_pixel(40, 10)[1] > 245);
+
+    //Top to bottom
+    gradient.set_angle(90.0);
+    let layer = custom_gradient_layer(&gradient);
+    assert_eq!(layer.get_pixel(0, 10), layer.get_pixel(40, 10));
+    assert!(layer.get_pixel(20, 0)[0] < layer.get_pixel(20, 20)[0]);
+}
+
+#[test]
+fn custom_gradient_radial_from_center() {
+    let mut gradient = Gradient::new(GradientType::RADIAL);
+    gradient.add_stop(0.0, ColorRgba::new(255, 255, 255, 255));
+    gradient.add_stop(1.0, ColorRgba::new(0, 0, 0, 255));
+
+    let layer = custom_gradient_layer(&gradient);
+    assert!(layer.get_pixel(20, 10)[0] > 245);
+    assert!(layer.get_pixel(0, 0)[0] < 20);
+    assert_eq!(layer.get_pixel(0, 0), layer.get_pixel(40, 20));
+}
+
+#[test]
+fn custom_gradient_without_stop() {
+    let mut img = DynamicImage::ImageRgba8(RgbaImage::new(4, 4));
+    let gradient = Gradient::new(GradientType::CONIC);
+    assert!(image_filters::filter_custom_gradient(&mut img, &gradient).is_err());
+}
+
+fn band_color_layer(direction: GradientDirection, softness: f32) -> RgbaImage {
+    let colors = vec![
+        Rgba([255, 0, 0, 255]),
+        Rgba([0, 255, 0, 255]),
+        Rgba([0, 0, 255, 255]),
+    ];
+    let mut img = DynamicImage::ImageRgba8(RgbaImage::new(31, 17));
+    image_filters::filter_band_color(&mut img, colors, direction, softness, BlendMode::NORMAL)
+        .unwrap()
+        .to_rgba8()
+}
+
+#[test]
+fn band_color_horizontal_bands_cover_the_image() {
+    let layer = band_color_layer(GradientDirection::HORIZONTAL, 0.0);
+    for (x, y, pixel) in layer.enumerate_pixels() {
+        //Every row has a single color, the 17 rows are split in 6 + 5 + 6
+        assert_eq!(pixel, layer.get_pixel(0, y), "({}, {})", x, y);
+        assert_eq!(pixel[3], 255);
+    }
+    assert_eq!(*layer.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+    assert_eq!(*layer.get_pixel(0, 8), Rgba([0, 255, 0, 255]));
+    assert_eq!(*layer.get_pixel(0, 16), Rgba([0, 0, 255, 255]));
+}
+
+#[test]
+fn band_color_vertical_bands_cover_the_image() {
+    let layer = band_color_layer(GradientDirection::VERTICAL, 0.0);
+    for (x, y, pixel) in layer.enumerate_pixels() {
+        assert_eq!(pixel, layer.get_pixel(x, 0), "({}, {})", x, y);
+        assert_eq!(pixel[3], 255);
+    }
+    assert_eq!(*layer.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+    assert_eq!(*layer.get_pixel(15, 0), Rgba([0, 255, 0, 255]));
+    assert_eq!(*layer.get_pixel(30, 0), Rgba([0, 0, 255, 255]));
+}
+
+#[test]
+fn band_color_softness_blends_edges() {
+    let hard = band_color_layer(GradientDirection::VERTICAL, 0.0);
+    let soft = band_color_layer(GradientDirection::VERTICAL, 0.5);
+    //Edges are mixed, the middle of the bands is untouched
+    let edge = soft.get_pixel(10, 0);
+    assert!(edge[0] > 0 && edge[1] > 0);
+    assert_eq!(hard.get_pixel(15, 0), soft.get_pixel(15, 0));
+}
+
+#[test]
+fn palette_presets() {
+    for name in ColorPalette::preset_names() {
+        assert!(!ColorPalette::from_preset(&name).unwrap().is_empty());
+    }
+    assert!(ColorPalette::from_preset("unknown").is_err());
+}
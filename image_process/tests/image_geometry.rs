@@ -0,0 +1,74 @@
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use image_process::engine::image_filters::ColorRgba;
+use image_process::engine::{ErrorCode, ErrorStage, Interpolation, Operation, Pipeline};
+
+fn sample_image() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(40, 20, |x, y| {
+        image::Rgba([(x * 6) as u8, (y * 12) as u8, 0, 255])
+    }))
+}
+
+#[test]
+fn crop_is_clamped_to_the_image() {
+    let crop = Operation::Crop {
+        x: 30,
+        y: 5,
+        width: 100,
+        height: 10,
+    };
+    let cropped = crop.apply(sample_image()).unwrap();
+    assert_eq!(cropped.dimensions(), (10, 10));
+    assert_eq!(cropped.to_rgba8().get_pixel(0, 0).0, [180, 60, 0, 255]);
+
+    let outside = Operation::Crop {
+        x: 50,
+        y: 0,
+        width: 10,
+        height: 10,
+    };
+    let error = outside
+        .apply(sample_image())
+        .expect_err("crop outside of the image");
+    assert!(error == ErrorCode::InvalidCrop);
+    assert_eq!(error.stage(), ErrorStage::PROCESS);
+    assert_eq!(error.detail("x"), Some("50"));
+    assert_eq!(error.detail("width"), Some("10"));
+    assert_eq!(error.detail("image_width"), Some("40"));
+    assert_eq!(error.detail("image_height"), Some("20"));
+}
+
+#[test]
+fn right_angle_rotations_and_flips() {
+    let img = sample_image();
+    let corner = *img.to_rgba8().get_pixel(39, 0);
+
+    let rotated = Operation::Rotate90.apply(img.clone()).unwrap();
+    assert_eq!(rotated.dimensions(), (20, 40));
+    assert_eq!(*rotated.to_rgba8().get_pixel(19, 39), corner);
+
+    let flipped = Operation::FlipHorizontal.apply(img.clone()).unwrap();
+    assert_eq!(*flipped.to_rgba8().get_pixel(0, 0), corner);
+
+    let json = r#"[{"op": "rotate180"}, {"op": "flip_vertical"}, {"op": "flip_horizontal"}]"#;
+    let back = Pipeline::from_json(json)
+        .unwrap()
+        .apply(img.clone())
+        .unwrap();
+    assert_eq!(back.to_rgba8(), img.to_rgba8());
+}
+
+#[test]
+fn free_rotation_fills_and_expands() {
+    let fill = ColorRgba::new(0, 0, 255, 255);
+    let same_size = Operation::rotate(45.0, Some(Interpolation::NEAREST), Some(fill), None)
+        .apply(sample_image())
+        .unwrap();
+    assert_eq!(same_size.dimensions(), (40, 20));
+    assert_eq!(same_size.to_rgba8().get_pixel(0, 0).0, [0, 0, 255, 255]);
+
+    let expanded = Operation::rotate(90.0, None, None, Some(true))
+        .apply(sample_image())
+        .unwrap();
+    assert_eq!(expanded.dimensions(), (20, 40));
+    assert_eq!(expanded.to_rgba8().get_pixel(10, 1)[3], 255);
+}
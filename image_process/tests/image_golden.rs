@@ -0,0 +1,112 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use image_process::engine::{Adjustment, ImageParameters};
+use std::path::PathBuf;
+
+//Largest difference allowed per channel, for the float rounding between platforms
+const TOLERANCE: i16 = 1;
+
+/// Hue from left to right, lightness from top to bottom, alpha fading on the last rows
+fn sample() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(48, 32, |x, y| {
+        let t = x as f32 / 47.0;
+        let l = y as f32 / 31.0;
+        let channel = |offset: f32| {
+            let v = ((t + offset) * std::f32::consts::TAU).cos() * 0.5 + 0.5;
+            (v * l * 255.0).round() as u8
+        };
+        let alpha = if y < 24 {
+            255
+        } else {
+            255 - (y - 24) as u8 * 30
+        };
+        Rgba([channel(0.0), channel(1.0 / 3.0), channel(2.0 / 3.0), alpha])
+    }))
+}
+
+/// Compare with tests/golden/`name`.png, written instead when UPDATE_GOLDEN is set
+fn assert_golden(name: &str, img: &DynamicImage) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect::<PathBuf>()
+        .with_extension("png");
+    let actual = img.to_rgba8();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(&path).unwrap();
+        return;
+    }
+    let expected = image::open(&path)
+        .unwrap_or_else(|e| panic!("{:?} : {} (run with UPDATE_GOLDEN=1)", path, e))
+        .to_rgba8();
+    assert_eq!(actual.dimensions(), expected.dimensions());
+    for ((x, y, a), e) in actual.enumerate_pixels().zip(expected.pixels()) {
+        for c in 0..4 {
+            let diff = (a[c] as i16 - e[c] as i16).abs();
+            assert!(
+                diff <= TOLERANCE,
+                "{} differs at ({}, {}) : {:?} instead of {:?}",
+                name,
+                x,
+                y,
+                a,
+                e
+            );
+        }
+    }
+}
+
+fn every_adjustment() -> ImageParameters {
+    let mut params = ImageParameters::new();
+    params.exposure = Some(0.3);
+    params.temperature = Some(20.0);
+    params.tint = Some(-10.0);
+    params.highlights = Some(-30.0);
+    params.shadows = Some(25.0);
+    params.brighten = Some(5);
+    params.constrast = Some(15.0);
+    params.gamma = Some(1.1);
+    params.saturation = Some(20.0);
+    params.vibrance = Some(30.0);
+    params.hue = Some(20);
+    params.blur = Some(0.8);
+    params.sharpen = Some(0.5);
+    params
+}
+
+#[test]
+fn golden_default_order() {
+    assert_golden("default_order", &every_adjustment().apply_filter(sample()));
+}
+
+#[test]
+fn golden_custom_order() {
+    let mut params = every_adjustment();
+    params.order = Some(vec![
+        Adjustment::SHARPEN,
+        Adjustment::BLUR,
+        Adjustment::HUE,
+        Adjustment::CONTRAST,
+    ]);
+    assert_golden("custom_order", &params.apply_filter(sample()));
+}
+
+#[test]
+fn golden_linear_light() {
+    let mut params = every_adjustment();
+    params.linear_light = Some(true);
+    assert_golden("linear_light", &params.apply_filter(sample()));
+}
+
+#[test]
+fn golden_grayscale_invert() {
+    let mut params = ImageParameters::new();
+    params.grayscale = Some(true);
+    params.invert = Some(true);
+    params.constrast = Some(-20.0);
+    assert_golden("grayscale_invert", &params.apply_filter(sample()));
+}
+
+#[test]
+fn golden_identity() {
+    let img = sample();
+    assert_eq!(ImageParameters::new().apply_filter(img.clone()), img);
+}
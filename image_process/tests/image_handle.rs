@@ -0,0 +1,118 @@
+mod common;
+
+use image::{DynamicImage, GenericImageView};
+use image_process::engine::{
+    ErrorCode, ImageHandle, ImageParameters, ImageProcess, Operation, OutputFormat, OutputOptions,
+    Pipeline,
+};
+
+fn sample() -> DynamicImage {
+    common::gradient(6, 4)
+}
+
+fn png_bytes() -> Vec<u8> {
+    OutputOptions::default().encode(&sample()).unwrap()
+}
+
+fn decode(bytes: Vec<u8>) -> DynamicImage {
+    ImageProcess::new(bytes)
+        .unwrap()
+        .get_dynamic_image()
+        .unwrap()
+}
+
+#[test]
+fn decoded_once_from_any_input() {
+    let from_bytes = ImageHandle::decode(png_bytes(), None).unwrap();
+    let from_base64 = ImageHandle::decode(base64::encode(png_bytes()), None).unwrap();
+
+    assert_eq!(from_bytes.image().to_rgb8(), sample().to_rgb8());
+    assert_eq!(from_base64.image().to_rgb8(), sample().to_rgb8());
+    assert_eq!(from_bytes.get_dimension().get_width(), 6);
+    assert_eq!(from_bytes.get_dimension().get_height(), 4);
+    assert_eq!(from_bytes.get_frame_count(), 1);
+    assert!(ImageHandle::decode(vec![1, 2, 3], None).is_err());
+}
+
+#[test]
+fn operations_chain_on_the_held_image() {
+    let mut handle = ImageHandle::decode(png_bytes(), None).unwrap();
+    handle.crop(1, 1, 4, 2).unwrap();
+    handle.rotate90().unwrap();
+    let mut params = ImageParameters::new();
+    params.invert = Some(true);
+    handle.apply_parameters(params).unwrap();
+
+    assert_eq!(handle.image().dimensions(), (2, 4));
+    let expected = sample().crop_imm(1, 1, 4, 2).rotate90();
+    let pixel = expected.get_pixel(0, 0);
+    assert_eq!(handle.image().get_pixel(0, 0)[0], 255 - pixel[0]);
+    assert_eq!(handle.history(), ["crop", "rotate90", "parameters"]);
+}
+
+#[test]
+fn failed_operation_keeps_the_image() {
+    let mut handle = ImageHandle::from_image(sample());
+    handle.flip_horizontal().unwrap();
+    let before = handle.image().clone();
+
+    let error = handle
+        .apply(&Operation::Crop {
+            x: 6,
+            y: 0,
+            width: 4,
+            height: 4,
+        })
+        .expect_err("crop outside of the image");
+    assert!(error == ErrorCode::InvalidCrop);
+    assert_eq!(handle.image(), &before);
+    assert_eq!(handle.history(), ["flip_horizontal"]);
+}
+
+#[test]
+fn export_encodes_the_current_state() {
+    let mut handle = ImageHandle::from_image(sample());
+    handle.flip_vertical().unwrap();
+
+    let png = handle.export(None).unwrap();
+    assert_eq!(png.get_format(), OutputFormat::PNG);
+    assert_eq!(decode(png.to_byte()).to_rgb8(), sample().flipv().to_rgb8());
+
+    let jpeg = handle
+        .export(Some(OutputOptions::new(OutputFormat::JPEG, Some(90))))
+        .unwrap();
+    assert_eq!(jpeg.mime_type(), "image/jpeg");
+    assert_eq!(decode(jpeg.to_byte()).dimensions(), (6, 4));
+
+    //Exporting twice gives the same bytes, the handle is not consumed
+    assert_eq!(handle.export(None).unwrap().to_byte(), png.to_byte());
+}
+
+#[test]
+fn preview_and_duplicate_leave_the_handle_untouched() {
+    let mut handle = ImageHandle::from_image(sample());
+    let mut params = ImageParameters::new();
+    params.grayscale = Some(true);
+
+    let preview = handle.preview_parameters(params, None).unwrap();
+    let gray = decode(preview.to_byte()).to_rgb8();
+    assert!(gray.pixels().all(|p| p[0] == p[1] && p[1] == p[2]));
+    assert_eq!(handle.image(), &sample());
+    assert!(handle.history().is_empty());
+
+    let copy = handle.duplicate();
+    handle.rotate180().unwrap();
+    assert_eq!(copy.image(), &sample());
+    assert!(copy.history().is_empty());
+}
+
+#[test]
+fn pipeline_is_applied_step_by_step() {
+    let mut handle = ImageHandle::from_image(sample());
+    let pipeline =
+        Pipeline::from_json(r#"[{"op": "rotate270"}, {"op": "flip_horizontal"}]"#).unwrap();
+    handle.apply_pipeline(&pipeline).unwrap();
+
+    assert_eq!(handle.image(), &sample().rotate270().fliph());
+    assert_eq!(handle.history(), ["rotate270", "flip_horizontal"]);
+}
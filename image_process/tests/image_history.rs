@@ -0,0 +1,146 @@
+mod common;
+
+use image::DynamicImage;
+use image_process::engine::{History, ImageHandle, ImageParameters, Operation};
+
+fn sample() -> DynamicImage {
+    common::gradient(6, 4)
+}
+
+fn operations() -> Vec<Operation> {
+    let mut invert = ImageParameters::new();
+    invert.invert = Some(true);
+    vec![
+        Operation::Rotate90,
+        Operation::FlipHorizontal,
+        Operation::Crop {
+            x: 0,
+            y: 1,
+            width: 3,
+            height: 4,
+        },
+        Operation::Parameters(invert),
+        Operation::Rotate270,
+        Operation::FlipVertical,
+        Operation::Crop {
+            x: 1,
+            y: 0,
+            width: 2,
+            height: 2,
+        },
+    ]
+}
+
+/// Apply every operation, return the image after each of them, the original first
+fn apply_all(handle: &mut ImageHandle) -> Vec<DynamicImage> {
+    let mut states = vec![handle.image().clone()];
+    for operation in operations() {
+        handle.apply(&operation).unwrap();
+        states.push(handle.image().clone());
+    }
+    states
+}
+
+#[test]
+fn undo_and_redo_restore_each_state() {
+    let mut handle = ImageHandle::from_image(sample());
+    let states = apply_all(&mut handle);
+
+    assert!(handle.undo().unwrap());
+    assert_eq!(handle.image(), &states[6]);
+    assert!(handle.undo().unwrap());
+    assert_eq!(handle.image(), &states[5]);
+    assert!(handle.redo().unwrap());
+    assert_eq!(handle.image(), &states[6]);
+    assert!(handle.redo().unwrap());
+    assert_eq!(handle.image(), &states[7]);
+    assert!(!handle.redo().unwrap());
+    assert!(!handle.can_redo());
+}
+
+#[test]
+fn undo_and_redo_across_snapshots() {
+    let mut handle = ImageHandle::from_image(sample());
+    handle.set_snapshot_interval(2);
+    let states = apply_all(&mut handle);
+
+    //Snapshots after 2, 4 and 6 operations, the undo replays from them or the original
+    for expected in states.iter().rev().skip(1) {
+        assert!(handle.undo().unwrap());
+        assert_eq!(handle.image(), expected);
+    }
+    assert!(!handle.undo().unwrap());
+    assert!(!handle.can_undo());
+
+    for expected in states.iter().skip(1) {
+        assert!(handle.redo().unwrap());
+        assert_eq!(handle.image(), expected);
+    }
+}
+
+#[test]
+fn snapshots_are_bounded() {
+    let mut history = History::new(sample());
+    history.set_snapshot_interval(1);
+    history.set_max_snapshots(3);
+    let mut image = sample();
+    let mut states = vec![image.clone()];
+    for operation in operations() {
+        image = operation.apply(image).unwrap();
+        history.push(operation, &image);
+        states.push(image.clone());
+    }
+    assert_eq!(history.snapshot_count(), 3);
+
+    //Before the oldest kept snapshot, rebuilt from the original
+    for expected in states.iter().rev().skip(1) {
+        assert_eq!(&history.undo().unwrap().unwrap(), expected);
+    }
+    assert_eq!(history.snapshot_count(), 3);
+
+    history.set_max_snapshots(1);
+    assert_eq!(history.snapshot_count(), 1);
+    history.set_max_snapshots(0);
+    assert_eq!(history.snapshot_count(), 0);
+}
+
+#[test]
+fn reset_can_be_undone() {
+    let mut handle = ImageHandle::from_image(sample());
+    handle.rotate90().unwrap();
+    handle.flip_horizontal().unwrap();
+    let edited = handle.image().clone();
+
+    handle.reset_to_original();
+    assert_eq!(handle.image(), &sample());
+    assert_eq!(handle.history(), ["rotate90", "flip_horizontal", "reset"]);
+    //Already at the original, not recorded twice
+    handle.reset_to_original();
+    assert_eq!(handle.history().len(), 3);
+
+    assert!(handle.undo().unwrap());
+    assert_eq!(handle.image(), &edited);
+    assert!(handle.redo().unwrap());
+    assert_eq!(handle.image(), &sample());
+
+    //The operations after the reset start from the original image
+    handle.flip_vertical().unwrap();
+    assert_eq!(handle.image(), &sample().flipv());
+    assert!(handle.undo().unwrap());
+    assert!(handle.undo().unwrap());
+    assert_eq!(handle.image(), &edited);
+}
+
+#[test]
+fn new_operation_drops_the_redo_steps() {
+    let mut handle = ImageHandle::from_image(sample());
+    handle.rotate90().unwrap();
+    handle.rotate90().unwrap();
+    assert!(handle.undo().unwrap());
+    assert!(handle.can_redo());
+
+    handle.flip_vertical().unwrap();
+    assert!(!handle.can_redo());
+    assert_eq!(handle.history(), ["rotate90", "flip_vertical"]);
+    assert_eq!(handle.image(), &sample().rotate90().flipv());
+}
@@ -0,0 +1,113 @@
+use image::{DynamicImage, GenericImageView, RgbImage};
+use image_process::engine::image_input::{self, DataUrl};
+use image_process::engine::{ErrorCode, ImageProcess, InputType, OutputFormat, OutputOptions};
+
+fn jpeg_bytes() -> Vec<u8> {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_fn(9, 7, |x, y| {
+        image::Rgb([(x * 20) as u8, (y * 30) as u8, 128])
+    }));
+    OutputOptions::new(OutputFormat::JPEG, Some(90))
+        .encode(&img)
+        .unwrap()
+}
+
+fn decoded_dimensions<T: InputType>(input: T) -> (u32, u32) {
+    ImageProcess::new(input)
+        .unwrap()
+        .get_dynamic_image()
+        .unwrap()
+        .dimensions()
+}
+
+#[test]
+fn data_url_with_any_mime_type() {
+    let data_url = format!("data:image/jpeg;base64,{}", base64::encode(jpeg_bytes()));
+    assert_eq!(decoded_dimensions(data_url), (9, 7));
+
+    let parsed = DataUrl::parse("DATA:image/webp;charset=utf-8;Base64,AAAA").unwrap();
+    assert_eq!(parsed.mime_type, "image/webp");
+    assert!(parsed.is_base64);
+    assert_eq!(parsed.data, "AAAA");
+    assert!(DataUrl::parse("AAAA").is_none());
+}
+
+#[test]
+fn percent_encoded_data_url() {
+    let encoded: String = jpeg_bytes().iter().map(|b| format!("%{:02X}", b)).collect();
+    let data_url = format!("data:image/jpeg,{}", encoded);
+    assert_eq!(decoded_dimensions(data_url), (9, 7));
+    assert!(image_input::decode_percent("%4").is_err());
+    assert!(image_input::decode_percent("%+5").is_err());
+    assert!(image_input::decode_percent("%-1").is_err());
+    assert_eq!(image_input::decode_percent("a%2fb%2F").unwrap(), b"a/b/");
+}
+
+#[test]
+fn url_safe_and_whitespace_base64() {
+    let bytes = jpeg_bytes();
+    let url_safe = base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD);
+    assert_eq!(image_input::decode_base64(&url_safe).unwrap(), bytes);
+
+    let wrapped: String = base64::encode(&bytes)
+        .as_bytes()
+        .chunks(76)
+        .map(|line| format!("{}\r\n", std::str::from_utf8(line).unwrap()))
+        .collect();
+    assert_eq!(decoded_dimensions(format!("  {}", wrapped)), (9, 7));
+    assert!(image_input::decode_base64("not base64 !").is_err());
+}
+
+#[test]
+fn raw_bytes_input() {
+    assert_eq!(decoded_dimensions(jpeg_bytes()), (9, 7));
+}
+
+#[test]
+fn weight_from_base64_without_decoding() {
+    let bytes = jpeg_bytes();
+    let encoded = base64::encode(&bytes);
+    for input in [
+        encoded.clone(),
+        format!("data:image/jpeg;base64,{}", encoded),
+        format!("data:image/webp;base64,{}", encoded.trim_end_matches('=')),
+    ] {
+        assert_eq!(
+            ImageProcess::get_image_weight_byte(&input).unwrap(),
+            bytes.len()
+        );
+    }
+    for (short, expected) in [("QQ==", 1), ("QUI=", 2), ("QUJD", 3)] {
+        assert_eq!(
+            ImageProcess::get_image_weight_byte(short).unwrap(),
+            expected
+        );
+    }
+    assert!(ImageProcess::get_image_weight_byte("").is_err());
+    assert!(ImageProcess::get_image_weight_byte("data:image/png;base64,").is_err());
+}
+
+#[test]
+fn weight_rejects_invalid_base64() {
+    let error = ImageProcess::get_image_weight_byte("not base64!!").expect_err("invalid base64");
+    assert!(error == ErrorCode::InvalidParsing);
+    assert_eq!(error.detail("position"), Some("10"));
+    assert_eq!(error.detail("character"), Some("!"));
+    assert!(ImageProcess::get_image_weight_byte("data:image/png;base64,QU.J").is_err());
+    //URL-safe digits and line breaks are accepted
+    assert_eq!(
+        ImageProcess::get_image_weight_byte("Q-_J\r\nQUJD").unwrap(),
+        6
+    );
+}
+
+#[test]
+fn encoded_and_decoded_size() {
+    let bytes = jpeg_bytes();
+    let size = ImageProcess::new(bytes.clone())
+        .unwrap()
+        .get_image_size()
+        .unwrap();
+    assert_eq!(size.get_encoded_bytes(), bytes.len());
+    assert_eq!(size.get_bits_per_pixel(), 24);
+    assert_eq!(size.get_decoded_bytes(), 9 * 7 * 3);
+}
@@ -0,0 +1,153 @@
+use image::codecs::gif::GifEncoder;
+use image::{DynamicImage, Frame, GenericImageView, RgbImage, RgbaImage};
+use image_process::engine::{DecodeLimits, ErrorCode, ImageProcess, OutputFormat, OutputOptions};
+
+fn png_bytes(w: u32, h: u32) -> Vec<u8> {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(w, h, image::Rgb([1, 2, 3])));
+    OutputOptions::new(OutputFormat::PNG, None)
+        .encode(&img)
+        .unwrap()
+}
+
+//Small PNG whose IHDR declares a huge image
+fn png_bomb(w: u32, h: u32) -> Vec<u8> {
+    let mut bytes = png_bytes(1, 1);
+    bytes[16..20].copy_from_slice(&w.to_be_bytes());
+    bytes[20..24].copy_from_slice(&h.to_be_bytes());
+    let crc = crc32fast::hash(&bytes[12..29]);
+    bytes[29..33].copy_from_slice(&crc.to_be_bytes());
+    bytes
+}
+
+fn gif_bytes(frames: u32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut bytes);
+        for i in 0..frames {
+            let frame = RgbaImage::from_pixel(4, 4, image::Rgba([i as u8 * 40, 0, 0, 255]));
+            encoder.encode_frame(Frame::new(frame)).unwrap();
+        }
+    }
+    bytes
+}
+
+#[test]
+fn huge_declared_size_is_rejected_before_decoding() {
+    let error = ImageProcess::new(png_bomb(50_000, 50_000))
+        .unwrap()
+        .with_limits(DecodeLimits::default())
+        .get_dynamic_image()
+        .expect_err("the bomb should be rejected");
+
+    assert!(error == ErrorCode::LimitExceeded);
+    assert_eq!(error.code().code(), "LIMIT_EXCEEDED");
+    assert_eq!(error.detail("limit"), Some("width"));
+    assert_eq!(error.detail("requested"), Some("50000"));
+    assert_eq!(error.detail("max"), Some("16384"));
+}
+
+#[test]
+fn pixels_and_allocation_limits() {
+    let process = ImageProcess::new(png_bytes(16, 16)).unwrap();
+
+    let limits = DecodeLimits::new(None, None, Some(100), None, None);
+    let error = ImageProcess::new(process.as_byte())
+        .unwrap()
+        .with_limits(limits)
+        .get_dynamic_image()
+        .expect_err("256 pixels is above 100");
+    assert_eq!(error.detail("limit"), Some("pixels"));
+    assert_eq!(error.detail("requested"), Some("256"));
+
+    let limits = DecodeLimits::new(None, None, None, Some(16 * 16 * 3 - 1), None);
+    let error = ImageProcess::new(process.as_byte())
+        .unwrap()
+        .with_limits(limits)
+        .get_dynamic_image()
+        .expect_err("768 bytes is above 767");
+    assert_eq!(error.detail("limit"), Some("alloc_bytes"));
+
+    let decoded = process
+        .with_limits(DecodeLimits::default())
+        .get_dynamic_image();
+    assert_eq!(decoded.unwrap().dimensions(), (16, 16));
+}
+
+#[test]
+fn frames_limit() {
+    let limits = DecodeLimits::new(None, None, None, None, Some(2));
+    let error = ImageProcess::new(gif_bytes(3))
+        .unwrap()
+        .with_limits(limits)
+        .get_dynamic_image()
+        .expect_err("3 frames is above 2");
+    assert!(error == ErrorCode::LimitExceeded);
+    assert_eq!(error.detail("limit"), Some("frames"));
+
+    let decoded = ImageProcess::new(gif_bytes(2))
+        .unwrap()
+        .with_limits(limits)
+        .get_dynamic_image();
+    assert!(decoded.is_ok());
+}
+
+#[test]
+fn unlimited_only_relies_on_the_decoder() {
+    let process = ImageProcess::new(png_bytes(8, 8))
+        .unwrap()
+        .with_limits(DecodeLimits::unlimited());
+    assert_eq!(process.get_dynamic_image().unwrap().dimensions(), (8, 8));
+    assert_eq!(DecodeLimits::unlimited().image_limits().max_alloc, None);
+}
+
+//Animated WebP of a 1x1 canvas, each frame declared with `frame` size by its ANMF chunk
+//and `bitstream` size by its VP8L header, without pixel data
+fn webp_frames_bomb(frame: u32, bitstream: u32) -> Vec<u8> {
+    let chunk = |kind: &[u8], data: &[u8]| {
+        let mut bytes = kind.to_vec();
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        bytes.resize(bytes.len() + data.len() % 2, 0);
+        bytes
+    };
+    let u24 = |value: u32| value.to_le_bytes()[..3].to_vec();
+    let mut vp8l = vec![0x2f];
+    vp8l.extend(((bitstream - 1) | (bitstream - 1) << 14).to_le_bytes());
+    let mut anmf = Vec::new();
+    for value in [0, 0, frame - 1, frame - 1, 100] {
+        anmf.extend(u24(value));
+    }
+    anmf.push(0);
+    anmf.extend(chunk(b"VP8L", &vp8l));
+
+    let mut vp8x = vec![0x02, 0, 0, 0];
+    vp8x.extend(u24(0));
+    vp8x.extend(u24(0));
+    let mut body = b"WEBP".to_vec();
+    body.extend(chunk(b"VP8X", &vp8x));
+    body.extend(chunk(b"ANIM", &[0; 6]));
+    body.extend(chunk(b"ANMF", &anmf));
+    body.extend(chunk(b"ANMF", &anmf));
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend((body.len() as u32).to_le_bytes());
+    bytes.extend(body);
+    bytes
+}
+
+#[test]
+fn webp_frames_larger_than_the_canvas_are_rejected() {
+    let limits = DecodeLimits::new(Some(100), Some(100), None, Some(40_000), None);
+    for (bytes, cause) in [
+        (webp_frames_bomb(3000, 3000), "outside of the canvas"),
+        (webp_frames_bomb(1, 3000), "differs from the ANMF header"),
+    ] {
+        let process = ImageProcess::new(bytes).unwrap().with_limits(limits);
+        let error = process
+            .get_dynamic_image()
+            .expect_err("the frame is larger than the canvas");
+        assert!(error == ErrorCode::UnableToDecode);
+        assert!(error.cause().unwrap().contains(cause));
+        assert_eq!(error.detail("frame"), Some("0"));
+        assert!(process.get_animation().is_err());
+    }
+}
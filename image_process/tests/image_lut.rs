@@ -0,0 +1,148 @@
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage, Rgba, RgbaImage};
+use image_process::engine::{ErrorCode, Lut3D, LutInterpolation, Operation, Pipeline};
+
+const INTERPOLATIONS: [LutInterpolation; 2] =
+    [LutInterpolation::TRILINEAR, LutInterpolation::TETRAHEDRAL];
+
+fn sample() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 16, |x, y| {
+        Rgba([(x * 17) as u8, (y * 17) as u8, ((x + y) * 8) as u8, 180])
+    }))
+}
+
+/// 2x2x2 LUT swapping red and blue
+fn swap_cube() -> String {
+    let mut cube = String::from("# Swap red and blue\nTITLE \"Swap\"\nLUT_3D_SIZE 2\n\n");
+    for b in 0..2 {
+        for g in 0..2 {
+            for r in 0..2 {
+                cube.push_str(&format!("{}.0 {}.0 {}.0\n", b, g, r));
+            }
+        }
+    }
+    cube
+}
+
+#[test]
+fn parse_cube() {
+    let lut = Lut3D::parse_cube(&swap_cube()).unwrap();
+    assert_eq!(lut.get_size(), 2);
+    assert_eq!(lut.get_title().as_deref(), Some("Swap"));
+
+    let missing = Lut3D::parse_cube("LUT_3D_SIZE 2\n0 0 0\n1 1 1\n").expect_err("too short");
+    assert!(missing == ErrorCode::InvalidParsing);
+    assert_eq!(missing.detail("expected"), Some("8"));
+
+    let invalid = Lut3D::parse_cube("LUT_3D_SIZE 2\n0 0 zero\n").expect_err("not a number");
+    assert_eq!(invalid.detail("line"), Some("2"));
+
+    //Rejected from the header, before reading any entry
+    let huge = Lut3D::parse_cube("LUT_3D_SIZE 256\n").expect_err("larger than 65");
+    assert_eq!(huge.detail("line"), Some("1"));
+    let largest = Lut3D::parse_cube("LUT_3D_SIZE 65\n").expect_err("no entries");
+    assert_eq!(largest.detail("expected"), Some("274625"));
+
+    let one_d = Lut3D::parse_cube("LUT_1D_SIZE 1024\n").expect_err("1D LUT");
+    assert!(one_d == ErrorCode::NotImplemented);
+}
+
+#[test]
+fn identity_keeps_the_colors() {
+    for interpolation in INTERPOLATIONS.iter() {
+        for size in [2, 17, 33].iter() {
+            let img = Lut3D::identity(*size).apply(sample(), *interpolation, 1.0);
+            for ((_, _, a), (_, _, e)) in img.pixels().zip(sample().pixels()) {
+                for c in 0..4 {
+                    assert!(
+                        (a[c] as i16 - e[c] as i16).abs() <= 1,
+                        "{:?}",
+                        interpolation
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn swap_and_intensity() {
+    let lut = Lut3D::parse_cube(&swap_cube()).unwrap();
+    for interpolation in INTERPOLATIONS.iter() {
+        let img = lut.apply(sample(), *interpolation, 1.0);
+        let [r, g, b, a] = sample().get_pixel(5, 9).0;
+        assert_eq!(img.get_pixel(5, 9).0, [b, g, r, a]);
+    }
+    let half = lut.apply(sample(), LutInterpolation::TETRAHEDRAL, 0.5);
+    let [r, _, b, _] = sample().get_pixel(15, 0).0;
+    let mixed = half.get_pixel(15, 0).0;
+    assert!(mixed[0].abs_diff(((r as u16 + b as u16) / 2) as u8) <= 1);
+    assert_eq!(
+        lut.apply(sample(), LutInterpolation::TRILINEAR, 0.0),
+        sample()
+    );
+}
+
+#[test]
+fn interpolations_differ_off_the_grid() {
+    //Only the white corner is changed, the tetrahedra spread it differently than the cube
+    let mut cube = String::from("LUT_3D_SIZE 2\n");
+    for i in 0..8 {
+        cube.push_str(if i == 7 { "1 1 1\n" } else { "0 0 0\n" });
+    }
+    let lut = Lut3D::parse_cube(&cube).unwrap();
+    let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([200, 100, 50])));
+    let trilinear = lut.apply(img.clone(), LutInterpolation::TRILINEAR, 1.0);
+    let tetrahedral = lut.apply(img, LutInterpolation::TETRAHEDRAL, 1.0);
+    assert_ne!(trilinear.get_pixel(0, 0), tetrahedral.get_pixel(0, 0));
+}
+
+#[test]
+fn hald_clut() {
+    //Level 2 : 8x8 pixels, 4 entries per side
+    let hald = DynamicImage::ImageRgb8(RgbImage::from_fn(8, 8, |x, y| {
+        let i = y * 8 + x;
+        let value = |v: u32| (v * 255 / 3) as u8;
+        //Inverted colors
+        Rgb([
+            255 - value(i % 4),
+            255 - value(i / 4 % 4),
+            255 - value(i / 16),
+        ])
+    }));
+    let lut = Lut3D::from_hald_image(&hald).unwrap();
+    assert_eq!(lut.get_size(), 4);
+    let img = lut.apply(sample(), LutInterpolation::TETRAHEDRAL, 1.0);
+    let [r, g, b, a] = sample().get_pixel(3, 7).0;
+    let inverted = img.get_pixel(3, 7).0;
+    for (c, v) in [r, g, b].iter().enumerate() {
+        assert!(inverted[c].abs_diff(255 - v) <= 1);
+    }
+    assert_eq!(inverted[3], a);
+
+    //Level 9 : 81 entries per side
+    let too_large = DynamicImage::ImageRgb8(RgbImage::new(729, 729));
+    assert!(Lut3D::from_hald_image(&too_large).is_err());
+
+    let not_hald = DynamicImage::ImageRgb8(RgbImage::new(10, 10));
+    assert!(
+        Lut3D::from_hald_image(&not_hald).expect_err("not a Hald CLUT")
+            == ErrorCode::InvalidParsing
+    );
+}
+
+#[test]
+fn pipeline_step() {
+    let json = format!(
+        r#"[{{"op": "lut", "lut": {:?}, "intensity": 1.0, "interpolation": "TRILINEAR"}}]"#,
+        swap_cube()
+    );
+    let pipeline = Pipeline::from_json(&json).unwrap();
+    assert_eq!(pipeline.operations()[0].name(), "lut");
+    let img = pipeline.apply(sample()).unwrap();
+    let [r, g, b, a] = sample().get_pixel(2, 3).0;
+    assert_eq!(img.get_pixel(2, 3).0, [b, g, r, a]);
+
+    let lut = Lut3D::parse_cube(&swap_cube()).unwrap();
+    let operation = Operation::lut(&lut, None, Some(0.0));
+    assert_eq!(operation.apply(sample()).unwrap(), sample());
+}
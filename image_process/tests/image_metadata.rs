@@ -0,0 +1,198 @@
+use image::codecs::gif::GifEncoder;
+use image::{DynamicImage, Frame, RgbImage, RgbaImage};
+use image_process::engine::{
+    ImageInfo, ImageProcess, MetadataOptions, OutputFormat, OutputOptions, ResizeOptions,
+};
+
+/// Big-endian TIFF entry : tag, type, count, inline value or offset
+fn entry(tiff: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: [u8; 4]) {
+    tiff.extend_from_slice(&tag.to_be_bytes());
+    tiff.extend_from_slice(&kind.to_be_bytes());
+    tiff.extend_from_slice(&count.to_be_bytes());
+    tiff.extend_from_slice(&value);
+}
+
+/// EXIF block with camera, orientation and GPS position (12°30' S, 45° E)
+fn exif_block() -> Vec<u8> {
+    let mut tiff = b"MM\0\x2a\0\0\0\x08".to_vec();
+    //IFD0 at 8 : 4 entries, then the GPS IFD at 62, then the data at 116
+    tiff.extend_from_slice(&4u16.to_be_bytes());
+    entry(&mut tiff, 0x010F, 2, 6, 116u32.to_be_bytes());
+    entry(&mut tiff, 0x0110, 2, 3, *b"X1\0\0");
+    entry(&mut tiff, 0x0112, 3, 1, [0, 6, 0, 0]);
+    entry(&mut tiff, 0x8825, 4, 1, 62u32.to_be_bytes());
+    tiff.extend_from_slice(&[0; 4]);
+
+    tiff.extend_from_slice(&4u16.to_be_bytes());
+    entry(&mut tiff, 0x0001, 2, 2, *b"S\0\0\0");
+    entry(&mut tiff, 0x0002, 5, 3, 122u32.to_be_bytes());
+    entry(&mut tiff, 0x0003, 2, 2, *b"E\0\0\0");
+    entry(&mut tiff, 0x0004, 5, 3, 146u32.to_be_bytes());
+    tiff.extend_from_slice(&[0; 4]);
+    assert_eq!(tiff.len(), 116);
+
+    tiff.extend_from_slice(b"Canon\0");
+    for (num, den) in [(12u32, 1u32), (30, 1), (0, 1), (45, 1), (0, 1), (0, 1)] {
+        tiff.extend_from_slice(&num.to_be_bytes());
+        tiff.extend_from_slice(&den.to_be_bytes());
+    }
+    let mut exif = b"Exif\0\0".to_vec();
+    exif.extend_from_slice(&tiff);
+    exif
+}
+
+fn jpeg_with_exif() -> Vec<u8> {
+    let img = DynamicImage::ImageRgb8(RgbImage::new(30, 10));
+    let jpeg = OutputOptions::new(OutputFormat::JPEG, None)
+        .encode(&img)
+        .unwrap();
+    let mut icc = b"ICC_PROFILE\0\x01\x01".to_vec();
+    icc.extend_from_slice(&[7; 64]);
+
+    let mut bytes = jpeg[..2].to_vec();
+    for (marker, data) in [(0xE1, exif_block()), (0xE2, icc)] {
+        bytes.extend_from_slice(&[0xFF, marker]);
+        bytes.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+        bytes.extend_from_slice(&data);
+    }
+    bytes.extend_from_slice(&jpeg[2..]);
+    bytes
+}
+
+/// Resize the JPEG to `format` with the metadata options, then read its info
+fn output_info(format: OutputFormat, metadata: MetadataOptions) -> ImageInfo {
+    let mut output = OutputOptions::new(format, None);
+    output.metadata = metadata;
+    let result = ImageProcess::new(jpeg_with_exif())
+        .unwrap()
+        .resize(20, 20, ResizeOptions::default(), output)
+        .unwrap();
+    ImageProcess::new(result.to_byte())
+        .unwrap()
+        .get_image_info()
+        .unwrap()
+}
+
+#[test]
+fn png_header() {
+    let img = DynamicImage::ImageRgba16(image::ImageBuffer::new(7, 3));
+    let info = ImageProcess::new(OutputOptions::default().encode(&img).unwrap())
+        .unwrap()
+        .get_image_info()
+        .unwrap();
+
+    assert_eq!(info.get_format(), "PNG");
+    assert_eq!((info.get_width(), info.get_height()), (7, 3));
+    assert_eq!(info.get_color_type(), "Rgba16");
+    assert_eq!(info.get_bit_depth(), 16);
+    assert!(info.has_alpha());
+    assert_eq!(info.get_frame_count(), 1);
+    assert!(!info.has_icc_profile());
+    assert!(info.get_exif().is_none());
+}
+
+#[test]
+fn jpeg_exif_fields() {
+    let info = ImageProcess::new(jpeg_with_exif())
+        .unwrap()
+        .get_image_info()
+        .unwrap();
+    assert_eq!(info.get_format(), "JPEG");
+    assert_eq!((info.get_width(), info.get_height()), (30, 10));
+    assert_eq!(info.get_orientation(), 6);
+
+    let exif = info.get_exif().unwrap();
+    assert_eq!(exif.get_camera_make().as_deref(), Some("Canon"));
+    assert_eq!(exif.get_camera_model().as_deref(), Some("X1"));
+    assert_eq!(exif.get_gps_latitude(), Some(-12.5));
+    assert_eq!(exif.get_gps_longitude(), Some(45.0));
+    assert_eq!(exif.get_iso(), None);
+}
+
+#[test]
+fn animated_gif_frame_count() {
+    let mut gif = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut gif);
+        let frames = (0..3).map(|i| {
+            Frame::new(RgbaImage::from_pixel(
+                4,
+                4,
+                image::Rgba([i * 80, 0, 0, 255]),
+            ))
+        });
+        encoder.encode_frames(frames).unwrap();
+    }
+    let info = ImageProcess::new(gif).unwrap().get_image_info().unwrap();
+    assert_eq!(info.get_format(), "GIF");
+    assert_eq!(info.get_frame_count(), 3);
+}
+
+#[test]
+fn webp_header_without_decoding() {
+    //Lossless header of a 300x200 image with alpha, no pixel data
+    let bits: u32 = 299 | (199 << 14) | (1 << 28);
+    let mut chunk = vec![0x2F];
+    chunk.extend_from_slice(&bits.to_le_bytes());
+    let mut webp = b"RIFF".to_vec();
+    webp.extend_from_slice(&(4 + 8 + chunk.len() as u32).to_le_bytes());
+    webp.extend_from_slice(b"WEBPVP8L");
+    webp.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+    webp.extend_from_slice(&chunk);
+
+    let info = ImageProcess::new(webp).unwrap().get_image_info().unwrap();
+    assert_eq!(info.get_format(), "WEBP");
+    assert_eq!((info.get_width(), info.get_height()), (300, 200));
+    assert!(info.has_alpha());
+}
+
+#[test]
+fn metadata_is_stripped_by_default() {
+    let info = output_info(OutputFormat::JPEG, MetadataOptions::default());
+    assert!(info.get_exif().is_none());
+    assert!(!info.has_icc_profile());
+}
+
+#[test]
+fn keep_all_metadata() {
+    for format in [OutputFormat::JPEG, OutputFormat::PNG] {
+        let info = output_info(format, MetadataOptions::keep_all());
+        assert!(info.has_icc_profile());
+        //The pixels are upright, the orientation is reset
+        assert_eq!(info.get_orientation(), 1);
+        assert_eq!((info.get_width(), info.get_height()), (7, 20));
+
+        let exif = info.get_exif().unwrap();
+        assert_eq!(exif.get_camera_make().as_deref(), Some("Canon"));
+        assert_eq!(exif.get_gps_latitude(), Some(-12.5));
+    }
+}
+
+#[test]
+fn whitelist_drops_gps() {
+    let mut metadata = MetadataOptions::strip_all();
+    metadata.exif = true;
+    let info = output_info(OutputFormat::JPEG, metadata);
+    assert!(!info.has_icc_profile());
+
+    let exif = info.get_exif().unwrap();
+    assert_eq!(exif.get_camera_model().as_deref(), Some("X1"));
+    assert_eq!(exif.get_gps_latitude(), None);
+}
+
+#[test]
+fn huge_chunk_length_stops_the_scan() {
+    //Valid PNG followed by a chunk declaring 4 GB of data
+    let mut png = OutputOptions::default()
+        .encode(&DynamicImage::new_rgb8(3, 2))
+        .unwrap();
+    let end = png.len() - 12;
+    let mut chunk = u32::MAX.to_be_bytes().to_vec();
+    chunk.extend_from_slice(b"eXIf\0\0\0\0");
+    png.splice(end..end, chunk);
+
+    let info = ImageProcess::new(png).unwrap().get_image_info().unwrap();
+    assert_eq!((info.get_width(), info.get_height()), (3, 2));
+    assert!(info.get_exif().is_none());
+    assert!(!info.has_icc_profile());
+}
@@ -0,0 +1,90 @@
+use image::{DynamicImage, GenericImageView, RgbImage};
+use image_process::engine::image_orientation::{self, ORIENTATION_NORMAL};
+use image_process::engine::{ImageHandle, ImageProcess, OutputFormat, OutputOptions};
+
+/// 30x10 JPEG with an APP1 segment holding the EXIF orientation
+fn jpeg_with_orientation(orientation: u16) -> Vec<u8> {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_fn(30, 10, |x, _| {
+        image::Rgb([(x * 8) as u8, 0, 0])
+    }));
+    let jpeg = OutputOptions::new(OutputFormat::JPEG, Some(90))
+        .encode(&img)
+        .unwrap();
+
+    let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+    exif.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+    exif.extend_from_slice(&orientation.to_be_bytes());
+    exif.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+    let mut bytes = jpeg[..2].to_vec();
+    bytes.extend_from_slice(&[0xFF, 0xE1]);
+    bytes.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+    bytes.extend_from_slice(&exif);
+    bytes.extend_from_slice(&jpeg[2..]);
+    bytes
+}
+
+#[test]
+fn orientation_is_read_from_exif() {
+    assert_eq!(
+        image_orientation::read_orientation(&jpeg_with_orientation(6)),
+        6
+    );
+    let plain = OutputOptions::default()
+        .encode(&DynamicImage::new_rgb8(2, 2))
+        .unwrap();
+    assert_eq!(
+        image_orientation::read_orientation(&plain),
+        ORIENTATION_NORMAL
+    );
+}
+
+#[test]
+fn decode_applies_orientation_unless_disabled() {
+    let process = ImageProcess::new(jpeg_with_orientation(6)).unwrap();
+    assert_eq!(process.get_orientation(), 6);
+    assert_eq!(process.get_dynamic_image().unwrap().dimensions(), (10, 30));
+
+    let raw = ImageProcess::new(jpeg_with_orientation(6))
+        .unwrap()
+        .with_auto_orient(false);
+    assert_eq!(raw.get_dynamic_image().unwrap().dimensions(), (30, 10));
+}
+
+#[test]
+fn every_orientation_is_upright() {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_fn(3, 2, |x, y| {
+        image::Rgb([x as u8, y as u8, 0])
+    }));
+    //Pixel displayed at the top left corner for each stored orientation
+    let expected = [
+        (1, [0, 0]),
+        (2, [2, 0]),
+        (3, [2, 1]),
+        (4, [0, 1]),
+        (5, [0, 0]),
+        (6, [0, 1]),
+        (7, [2, 1]),
+        (8, [2, 0]),
+    ];
+    for (orientation, [x, y]) in expected {
+        let oriented = image_orientation::apply_orientation(img.clone(), orientation).to_rgb8();
+        assert_eq!(
+            oriented.get_pixel(0, 0).0,
+            [x, y, 0],
+            "orientation {}",
+            orientation
+        );
+    }
+}
+
+#[test]
+fn handle_can_keep_the_stored_orientation() {
+    let oriented = ImageHandle::decode(jpeg_with_orientation(6), Some(true)).unwrap();
+    assert_eq!(oriented.image().dimensions(), (10, 30));
+    let raw = ImageHandle::decode(jpeg_with_orientation(6), Some(false)).unwrap();
+    assert_eq!(raw.image().dimensions(), (30, 10));
+    //Without option the global default applies, enabled unless `set_auto_orient(false)`
+    let default = ImageHandle::decode(jpeg_with_orientation(6), None).unwrap();
+    assert_eq!(default.image().dimensions(), (10, 30));
+}
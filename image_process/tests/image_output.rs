@@ -0,0 +1,142 @@
+use image::{DynamicImage, GenericImageView, ImageFormat, RgbImage};
+use image_process::engine::{ImageProcess, OutputFormat, OutputOptions, ResizeOptions};
+
+fn sample_image() -> ImageProcess {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_fn(6, 4, |x, y| {
+        image::Rgb([(x * 40) as u8, (y * 60) as u8, 90])
+    }));
+    ImageProcess::new(OutputOptions::default().encode(&img).unwrap()).unwrap()
+}
+
+#[test]
+fn result_is_a_data_url_of_its_format() {
+    let output = OutputOptions::new(OutputFormat::JPEG, Some(80));
+    let result = sample_image()
+        .resize(3, 2, ResizeOptions::default(), output)
+        .unwrap();
+
+    assert_eq!(result.get_format(), OutputFormat::JPEG);
+    assert_eq!(result.mime_type(), "image/jpeg");
+    assert_eq!(result.extension(), "jpg");
+    assert_eq!(result.byte_length(), result.as_bytes().len());
+
+    let data_url = result.to_data_url();
+    assert!(data_url.starts_with("data:image/jpeg;base64,"));
+    let decoded = ImageProcess::new(data_url)
+        .unwrap()
+        .get_dynamic_image()
+        .unwrap();
+    assert_eq!(decoded.dimensions(), (3, 2));
+}
+
+#[test]
+fn every_format_is_encoded_as_selected() {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_fn(16, 16, |x, y| {
+        image::Rgb([(x * 16) as u8, (y * 16) as u8, 90])
+    }));
+    for (format, expected) in [
+        (OutputFormat::PNG, ImageFormat::Png),
+        (OutputFormat::JPEG, ImageFormat::Jpeg),
+        (OutputFormat::WEBP, ImageFormat::WebP),
+        (OutputFormat::BMP, ImageFormat::Bmp),
+        (OutputFormat::TIFF, ImageFormat::Tiff),
+        (OutputFormat::GIF, ImageFormat::Gif),
+        (OutputFormat::FARBFELD, ImageFormat::Farbfeld),
+    ] {
+        let bytes = OutputOptions::new(format, None).encode(&img).unwrap();
+        assert_eq!(image::guess_format(&bytes).unwrap(), expected);
+        let decoded = image::load_from_memory_with_format(&bytes, expected).unwrap();
+        assert_eq!(decoded.dimensions(), (16, 16));
+    }
+}
+
+#[test]
+fn quality_only_changes_lossy_formats() {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_fn(64, 64, |x, y| {
+        image::Rgb([(x * 4) as u8, (y * 4) as u8, ((x * y) % 256) as u8])
+    }));
+    let size = |format, quality| {
+        OutputOptions::new(format, Some(quality))
+            .encode(&img)
+            .unwrap()
+            .len()
+    };
+    assert!(size(OutputFormat::JPEG, 20) < size(OutputFormat::JPEG, 95));
+    assert_eq!(size(OutputFormat::PNG, 20), size(OutputFormat::PNG, 95));
+    assert_eq!(size(OutputFormat::WEBP, 20), size(OutputFormat::WEBP, 95));
+    assert!(OutputFormat::JPEG.is_lossy());
+    assert!(!OutputFormat::PNG.is_lossy());
+    assert!(!OutputFormat::WEBP.is_lossy());
+    assert_eq!(OutputOptions::default().quality, 85);
+    assert_eq!(OutputOptions::default().format, OutputFormat::PNG);
+}
+
+#[test]
+fn mime_types_and_extensions() {
+    for (format, mime, extension) in [
+        (OutputFormat::PNG, "image/png", "png"),
+        (OutputFormat::JPEG, "image/jpeg", "jpg"),
+        (OutputFormat::WEBP, "image/webp", "webp"),
+        (OutputFormat::BMP, "image/bmp", "bmp"),
+        (OutputFormat::TIFF, "image/tiff", "tiff"),
+        (OutputFormat::GIF, "image/gif", "gif"),
+        (OutputFormat::FARBFELD, "image/x-farbfeld", "ff"),
+    ] {
+        assert_eq!(format.mime_type(), mime);
+        assert_eq!(format.extension(), extension);
+    }
+}
+
+#[test]
+fn sixteen_bits_images_are_converted_for_8_bits_formats() {
+    let img = DynamicImage::ImageRgba16(image::ImageBuffer::from_pixel(
+        4,
+        4,
+        image::Rgba([65535u16, 32768, 0, 65535]),
+    ));
+    for format in [
+        OutputFormat::JPEG,
+        OutputFormat::WEBP,
+        OutputFormat::BMP,
+        OutputFormat::GIF,
+    ] {
+        let bytes = OutputOptions::new(format, None).encode(&img).unwrap();
+        let pixel = image::load_from_memory(&bytes)
+            .unwrap()
+            .to_rgb8()
+            .get_pixel(1, 1)
+            .0;
+        assert!(
+            pixel[0] > 240 && pixel[2] < 15,
+            "{:?} : {:?}",
+            format,
+            pixel
+        );
+    }
+}
+
+#[test]
+fn webp_is_lossless() {
+    let img = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(16, 16, |x, y| {
+        image::Rgba([(x * 16) as u8, (y * 16) as u8, 90, (x * y) as u8])
+    }));
+    let bytes = OutputOptions::new(OutputFormat::WEBP, Some(10))
+        .encode(&img)
+        .unwrap();
+    let decoded = ImageProcess::new(bytes)
+        .unwrap()
+        .get_dynamic_image()
+        .unwrap();
+    assert_eq!(decoded.to_rgba8(), img.to_rgba8());
+}
+
+#[test]
+fn bytes_can_be_moved_out_of_the_result() {
+    let result = sample_image()
+        .resize(3, 2, ResizeOptions::default(), OutputOptions::default())
+        .unwrap();
+    let copy = result.to_byte();
+    let bytes = result.take_bytes();
+    assert_eq!(bytes, copy);
+    assert_eq!(image::guess_format(&bytes).unwrap(), ImageFormat::Png);
+}
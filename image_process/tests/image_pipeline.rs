@@ -0,0 +1,134 @@
+mod common;
+
+use image::{DynamicImage, GenericImageView};
+use image_process::engine::image_filters::{self, FilterPixelType};
+use image_process::engine::{ErrorCode, ErrorStage, Operation, Pipeline};
+
+fn sample() -> DynamicImage {
+    common::gradient(4, 2)
+}
+
+#[test]
+fn operations_are_parsed_in_order() {
+    let json = r#"[
+        {"op": "parameters", "brighten": 10, "grayscale": true},
+        {"op": "resize", "width": 8, "height": 8, "mode": "FILL"},
+        {"op": "sobel"},
+        {"op": "flip_horizontal"}
+    ]"#;
+    let pipeline = Pipeline::from_json(json).unwrap();
+    let names: Vec<&str> = pipeline.operations().iter().map(|op| op.name()).collect();
+    assert_eq!(names, ["parameters", "resize", "sobel", "flip_horizontal"]);
+    assert_eq!(pipeline.len(), 4);
+
+    match &pipeline.operations()[0] {
+        Operation::Parameters(params) => {
+            assert_eq!(params.brighten, Some(10));
+            assert_eq!(params.grayscale, Some(true));
+            assert_eq!(params.blur, None);
+        }
+        other => panic!("unexpected operation {:?}", other),
+    }
+    let empty = Pipeline::from_json("[]").unwrap();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn unknown_operation_is_an_error() {
+    let error = Pipeline::parse_operations(r#"[{"op": "sobel"}, {"op": "sharpen_more"}]"#)
+        .expect_err("unknown op should fail");
+    assert!(error == ErrorCode::InvalidParsing);
+    assert_eq!(error.stage(), ErrorStage::INPUT);
+    assert!(error.cause().unwrap().contains("sharpen_more"));
+
+    //Missing required field
+    let error = Pipeline::parse_operations(r#"[{"op": "crop", "x": 0, "y": 0}]"#)
+        .expect_err("crop needs a size");
+    assert!(error == ErrorCode::InvalidParsing);
+    assert!(error.cause().unwrap().contains("width"));
+
+    //Not an array of operations
+    assert!(Pipeline::parse_operations(r#"{"op": "sobel"}"#).is_err());
+}
+
+#[test]
+fn omitted_fields_use_the_defaults() {
+    let json = r#"[
+        {"op": "pixel", "pixel_type": "VERTICAL", "color": {"red": 1, "green": 2, "blue": 3, "alpha": 4}},
+        {"op": "band_color", "direction": "VERTICAL"},
+        {"op": "preset", "name": "noir"},
+        {"op": "grain", "amount": 0.5}
+    ]"#;
+    let operations = Pipeline::parse_operations(json).unwrap();
+
+    match &operations[0] {
+        Operation::Pixel {
+            pixel_type,
+            spacing,
+            thickness,
+            ..
+        } => {
+            assert!(matches!(pixel_type, FilterPixelType::VERTICAL));
+            assert_eq!(*spacing, image_filters::DEFAULT_PIXEL_SPACING);
+            assert_eq!(*thickness, image_filters::DEFAULT_PIXEL_THICKNESS);
+        }
+        other => panic!("unexpected operation {:?}", other),
+    }
+    match &operations[1] {
+        Operation::BandColor {
+            colors, softness, ..
+        } => {
+            assert_eq!(colors.len(), image_filters::default_band_colors().len());
+            assert_eq!(*softness, 0.0);
+        }
+        other => panic!("unexpected operation {:?}", other),
+    }
+    match &operations[2] {
+        Operation::Preset { name, intensity } => {
+            assert_eq!(name, "noir");
+            assert_eq!(*intensity, 1.0);
+        }
+        other => panic!("unexpected operation {:?}", other),
+    }
+    assert!(matches!(operations[3], Operation::Grain { seed: 0, .. }));
+}
+
+#[test]
+fn operations_run_in_order() {
+    let crop_then_flip = Pipeline::from_json(
+        r#"[{"op": "crop", "x": 0, "y": 0, "width": 2, "height": 2}, {"op": "flip_horizontal"}]"#,
+    )
+    .unwrap()
+    .apply(sample())
+    .unwrap();
+    let flip_then_crop = Pipeline::from_json(
+        r#"[{"op": "flip_horizontal"}, {"op": "crop", "x": 0, "y": 0, "width": 2, "height": 2}]"#,
+    )
+    .unwrap()
+    .apply(sample())
+    .unwrap();
+
+    assert_eq!(crop_then_flip.dimensions(), (2, 2));
+    assert_eq!(crop_then_flip.get_pixel(0, 0)[0], 60);
+    assert_eq!(flip_then_crop.get_pixel(0, 0)[0], 180);
+}
+
+#[test]
+fn built_and_parsed_pipelines_match() {
+    let mut built = Pipeline::new();
+    built.add_rotate90();
+    built.add_crop(0, 1, 2, 2);
+    built.add_grain(0.3, Some(4));
+    let parsed = Pipeline::from_json(
+        r#"[
+            {"op": "rotate90"},
+            {"op": "crop", "x": 0, "y": 1, "width": 2, "height": 2},
+            {"op": "grain", "amount": 0.3, "seed": 4}
+        ]"#,
+    )
+    .unwrap();
+
+    let built = built.apply(sample()).unwrap();
+    assert_eq!(built.dimensions(), (2, 2));
+    assert_eq!(built, parsed.apply(sample()).unwrap());
+}
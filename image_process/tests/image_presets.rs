@@ -0,0 +1,115 @@
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use image_process::engine::image_filters;
+use image_process::engine::image_presets::{self, PRESET_NAMES};
+use image_process::engine::{ErrorCode, Operation, Pipeline};
+
+fn sample() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 24, |x, y| {
+        Rgba([(x * 8) as u8, (y * 10) as u8, 160, 255])
+    }))
+}
+
+fn gray(value: u8) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        32,
+        24,
+        Rgba([value, value, value, 255]),
+    ))
+}
+
+#[test]
+fn every_preset_changes_the_image() {
+    for name in PRESET_NAMES.iter() {
+        let img = image_presets::apply_preset(sample(), name, 1.0).unwrap();
+        assert_eq!(img.dimensions(), (32, 24), "{}", name);
+        assert_ne!(img.to_rgba8(), sample().to_rgba8(), "{} does nothing", name);
+        //Same result for a preview and its export
+        let again = image_presets::apply_preset(sample(), name, 1.0).unwrap();
+        assert_eq!(img, again, "{} is not reproducible", name);
+    }
+}
+
+#[test]
+fn unknown_preset() {
+    let error = image_presets::apply_preset(sample(), "polaroid", 1.0).expect_err("unknown");
+    assert!(error == ErrorCode::UnknownPreset);
+    assert_eq!(error.detail("name"), Some("polaroid"));
+}
+
+#[test]
+fn intensity_mixes_with_the_original() {
+    assert_eq!(
+        image_presets::apply_preset(sample(), "warm", 0.0).unwrap(),
+        sample()
+    );
+    let full = image_presets::apply_preset(sample(), "warm", 1.0)
+        .unwrap()
+        .to_rgba8();
+    let half = image_presets::apply_preset(sample(), "warm", 0.5)
+        .unwrap()
+        .to_rgba8();
+    let original = sample().to_rgba8();
+    for ((o, f), h) in original.pixels().zip(full.pixels()).zip(half.pixels()) {
+        for c in 0..4 {
+            let expected = (o[c] as f32 + f[c] as f32) / 2.0;
+            assert!((h[c] as f32 - expected).abs() <= 1.0);
+        }
+    }
+}
+
+#[test]
+fn noir_and_sepia_tones() {
+    let noir = image_presets::apply_preset(sample(), "noir", 1.0)
+        .unwrap()
+        .to_rgba8();
+    assert!(noir.pixels().all(|p| p[0] == p[1] && p[1] == p[2]));
+
+    let sepia = image_presets::apply_preset(gray(128), "sepia", 1.0)
+        .unwrap()
+        .to_rgba8();
+    let [r, g, b, _] = sepia.get_pixel(16, 12).0;
+    assert!(r > g && g > b);
+}
+
+#[test]
+fn vignette_darkens_the_corners() {
+    let img = image_filters::filter_vignette(gray(200), 0.5, 0.4)
+        .unwrap()
+        .to_rgba8();
+    assert_eq!(img.get_pixel(16, 12)[0], 200);
+    assert!(img.get_pixel(0, 0)[0] < 120);
+    assert_eq!(img.get_pixel(0, 0)[3], 255);
+}
+
+#[test]
+fn grain_depends_on_the_seed() {
+    let first = image_filters::filter_grain(gray(128), 0.5, 1).unwrap();
+    let same = image_filters::filter_grain(gray(128), 0.5, 1).unwrap();
+    let other = image_filters::filter_grain(gray(128), 0.5, 2).unwrap();
+    assert_eq!(first, same);
+    assert_ne!(first, other);
+    let pixels = first.to_rgba8();
+    assert!(pixels.pixels().all(|p| p[0] == p[1] && p[1] == p[2]));
+    assert!(pixels.pixels().any(|p| p[0] != 128));
+    assert!(pixels.pixels().all(|p| p[0].abs_diff(128) <= 26));
+}
+
+#[test]
+fn pipeline_steps() {
+    let json = r#"[
+        {"op": "preset", "name": "lomo", "intensity": 0.5},
+        {"op": "vignette", "strength": 0.3},
+        {"op": "grain", "amount": 0.1}
+    ]"#;
+    let pipeline = Pipeline::from_json(json).unwrap();
+    let names: Vec<&str> = pipeline.operations().iter().map(|op| op.name()).collect();
+    assert_eq!(names, ["preset", "vignette", "grain"]);
+    assert!(pipeline.apply(sample()).is_ok());
+
+    let operation = Operation::preset("vintage", None);
+    assert_eq!(
+        operation.apply(sample()).unwrap(),
+        image_presets::apply_preset(sample(), "vintage", 1.0).unwrap()
+    );
+    assert!(Operation::preset("nope", None).apply(sample()).is_err());
+}
@@ -0,0 +1,104 @@
+use image::{DynamicImage, GenericImageView, RgbImage};
+use image_process::engine::image_filters::ColorRgba;
+use image_process::engine::{Gravity, Pipeline, ResizeMode, ResizeOptions};
+
+fn sample_image(w: u32, h: u32) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(w, h, |x, _| {
+        if x < w / 2 {
+            image::Rgb([255, 0, 0])
+        } else {
+            image::Rgb([0, 0, 255])
+        }
+    }))
+}
+
+fn with_mode(mode: ResizeMode) -> ResizeOptions {
+    ResizeOptions::new(Some(mode), None, None, None, None)
+}
+
+#[test]
+fn modes_dimensions() {
+    let img = sample_image(200, 100);
+    let fit = with_mode(ResizeMode::FIT).apply(&img, 50, 50);
+    assert_eq!(fit.dimensions(), (50, 25));
+
+    let fill = with_mode(ResizeMode::FILL).apply(&img, 50, 50);
+    assert_eq!(fill.dimensions(), (50, 50));
+
+    let exact = with_mode(ResizeMode::EXACT).apply(&img, 30, 70);
+    assert_eq!(exact.dimensions(), (30, 70));
+
+    let contain = with_mode(ResizeMode::CONTAIN).apply(&img, 50, 50);
+    assert_eq!(contain.dimensions(), (50, 50));
+
+    //A 0 side keeps the aspect ratio
+    let auto_height = with_mode(ResizeMode::EXACT).apply(&img, 100, 0);
+    assert_eq!(auto_height.dimensions(), (100, 50));
+}
+
+#[test]
+fn fill_crop_follows_gravity() {
+    let img = sample_image(200, 100);
+    let west = ResizeOptions::new(
+        Some(ResizeMode::FILL),
+        None,
+        Some(Gravity::WEST),
+        None,
+        None,
+    )
+    .apply(&img, 50, 50)
+    .to_rgba8();
+    let east = ResizeOptions::new(
+        Some(ResizeMode::FILL),
+        None,
+        Some(Gravity::EAST),
+        None,
+        None,
+    )
+    .apply(&img, 50, 50)
+    .to_rgba8();
+
+    assert_eq!(west.get_pixel(10, 25)[0], 255);
+    assert_eq!(east.get_pixel(40, 25)[2], 255);
+}
+
+#[test]
+fn contain_pads_with_background() {
+    let background = ColorRgba::new(0, 255, 0, 255);
+    let padded = ResizeOptions::new(
+        Some(ResizeMode::CONTAIN),
+        None,
+        Some(Gravity::NORTH),
+        Some(background),
+        None,
+    )
+    .apply(&sample_image(200, 100), 50, 50)
+    .to_rgba8();
+
+    assert_eq!(padded.get_pixel(25, 45).0, [0, 255, 0, 255]);
+    assert_ne!(padded.get_pixel(25, 5).0, [0, 255, 0, 255]);
+}
+
+#[test]
+fn only_shrink_never_enlarges() {
+    let img = sample_image(40, 20);
+    let options = ResizeOptions::new(Some(ResizeMode::FIT), None, None, None, Some(true));
+    assert_eq!(options.apply(&img, 400, 400).dimensions(), (40, 20));
+    assert_eq!(options.apply(&img, 20, 20).dimensions(), (20, 10));
+
+    //EXACT keeps the aspect ratio of the box, scaled down to the larger side
+    let exact = ResizeOptions::new(Some(ResizeMode::EXACT), None, None, None, Some(true));
+    let tall = sample_image(100, 400);
+    assert_eq!(exact.apply(&tall, 200, 200).dimensions(), (100, 100));
+    assert_eq!(exact.apply(&tall, 50, 100).dimensions(), (50, 100));
+    assert_eq!(exact.apply(&tall, 300, 600).dimensions(), (100, 200));
+}
+
+#[test]
+fn resize_options_from_json() {
+    let json =
+        r#"[{"op": "resize", "width": 50, "height": 50, "mode": "FILL", "filter": "NEAREST"}]"#;
+    let pipeline = Pipeline::from_json(json).unwrap();
+    let resized = pipeline.apply(sample_image(200, 100)).unwrap();
+    assert_eq!(resized.dimensions(), (50, 50));
+}
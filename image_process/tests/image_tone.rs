@@ -0,0 +1,153 @@
+use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba, RgbaImage};
+use image_process::engine::{
+    CurveChannel, CurvePoint, Curves, Levels, MonotoneSpline, Operation, Pipeline,
+};
+
+fn ramp() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(256, 1, |x, _| {
+        Rgba([x as u8, x as u8, 255 - x as u8, 200])
+    }))
+}
+
+fn pixel(img: &DynamicImage, x: u32) -> [u8; 4] {
+    img.get_pixel(x, 0).0
+}
+
+#[test]
+fn empty_curves_and_levels_keep_the_image() {
+    let img = ramp();
+    assert_eq!(Curves::new().apply(img.clone()), img);
+    assert_eq!(Levels::default().apply(img.clone()), img);
+}
+
+#[test]
+fn curve_goes_through_its_points() {
+    let mut curves = Curves::new();
+    curves.add_point(CurveChannel::RGB, 64.0, 40.0);
+    curves.add_point(CurveChannel::RGB, 192.0, 220.0);
+    let img = curves.apply(ramp());
+    assert_eq!(pixel(&img, 0)[0], 0);
+    assert_eq!(pixel(&img, 64)[0], 40);
+    assert_eq!(pixel(&img, 192)[0], 220);
+    assert_eq!(pixel(&img, 255)[0], 255);
+    //Alpha has no curve
+    assert_eq!(pixel(&img, 100)[3], 200);
+}
+
+#[test]
+fn curve_is_monotone() {
+    //A steep step would overshoot with a natural cubic spline
+    let points = [(0.0, 0.0), (100.0, 10.0), (110.0, 240.0), (255.0, 255.0)];
+    let mut curves = Curves::new();
+    for (x, y) in points.iter() {
+        curves.add_point(CurveChannel::RED, *x, *y);
+    }
+    let spline = curves.spline(CurveChannel::RED).unwrap();
+    let mut previous = 0.0;
+    for i in 0..=1000 {
+        let y = spline.eval(i as f32 / 1000.0);
+        assert!(y >= previous, "curve decreases at {}", i);
+        assert!(y <= 1.0);
+        previous = y;
+    }
+    //Flat between two equal points
+    let flat = MonotoneSpline::new(&[CurvePoint::new(50.0, 100.0), CurvePoint::new(150.0, 100.0)])
+        .unwrap();
+    assert!((flat.eval(100.0 / 255.0) - 100.0 / 255.0).abs() < 1e-6);
+}
+
+#[test]
+fn invalid_points_are_ignored() {
+    let mut curves = Curves::new();
+    curves.add_point(CurveChannel::RGB, f32::NAN, 100.0);
+    curves.add_point(CurveChannel::RED, 100.0, f32::INFINITY);
+    assert_eq!(curves.points_count(CurveChannel::RGB), 0);
+    assert_eq!(curves.points_count(CurveChannel::RED), 0);
+    assert_eq!(curves.apply(ramp()), ramp());
+
+    //Built without the constructor, like the deserialized points
+    let nan = CurvePoint {
+        x: f32::NAN,
+        y: 0.0,
+    };
+    assert!(MonotoneSpline::new(&[nan]).is_none());
+    let spline = MonotoneSpline::new(&[nan, CurvePoint { x: 128.0, y: 64.0 }]).unwrap();
+    assert!((spline.eval(128.0 / 255.0) - 64.0 / 255.0).abs() < 1e-6);
+
+    let curves: Curves = serde_json::from_str(r#"{"rgb": [{"x": -20, "y": 400}]}"#).unwrap();
+    let spline = curves.spline(CurveChannel::RGB).unwrap();
+    assert_eq!(spline.eval(0.0), 1.0);
+    assert_eq!(spline.eval(1.0), 1.0);
+}
+
+#[test]
+fn channel_curves() {
+    let mut curves = Curves::new();
+    curves.add_point(CurveChannel::BLUE, 255.0, 128.0);
+    curves.add_point(CurveChannel::ALPHA, 200.0, 100.0);
+    assert_eq!(curves.points_count(CurveChannel::BLUE), 1);
+    let img = curves.apply(ramp());
+    assert_eq!(pixel(&img, 0), [0, 0, 128, 100]);
+    assert_eq!(pixel(&img, 255), [255, 255, 0, 100]);
+    curves.clear(CurveChannel::ALPHA);
+    assert_eq!(pixel(&curves.apply(ramp()), 0)[3], 200);
+}
+
+#[test]
+fn levels() {
+    let levels = Levels::new(Some(50.0), Some(200.0), None, None, None);
+    let img = levels.apply(ramp());
+    assert_eq!(pixel(&img, 50)[0], 0);
+    assert_eq!(pixel(&img, 110)[0], 102);
+    assert_eq!(pixel(&img, 200)[0], 255);
+    assert_eq!(pixel(&img, 0)[3], 200);
+
+    let output = Levels::new(None, None, None, Some(20.0), Some(220.0));
+    let img = output.apply(ramp());
+    assert_eq!(pixel(&img, 0)[0], 20);
+    assert_eq!(pixel(&img, 255)[0], 220);
+
+    let gamma = Levels::new(None, None, Some(2.0), None, None);
+    assert!(pixel(&gamma.apply(ramp()), 64)[0] > 64);
+}
+
+#[test]
+fn precision_is_kept() {
+    let img = DynamicImage::ImageRgba16(ImageBuffer::from_pixel(
+        1,
+        1,
+        Rgba([1000u16, 2000, 3000, 65535]),
+    ));
+    let mut curves = Curves::new();
+    curves.add_point(CurveChannel::RGB, 0.0, 0.0);
+    let adjusted = curves.apply(img);
+    assert!(matches!(adjusted, DynamicImage::ImageRgba16(_)));
+    assert_eq!(
+        adjusted.to_rgba16().get_pixel(0, 0).0,
+        [1000, 2000, 3000, 65535]
+    );
+}
+
+#[test]
+fn pipeline_steps() {
+    let json = r#"[
+        {"op": "levels", "input_black": 50, "input_white": 200},
+        {"op": "curves", "rgb": [{"x": 128, "y": 64}], "alpha": [{"x": 200, "y": 255}]}
+    ]"#;
+    let pipeline = Pipeline::from_json(json).unwrap();
+    assert_eq!(pipeline.operations()[0].name(), "levels");
+    assert_eq!(pipeline.operations()[1].name(), "curves");
+    let img = pipeline.apply(ramp()).unwrap();
+    //125 is half the input range, then mid gray goes down to 64
+    let [r, g, _, a] = pixel(&img, 125);
+    assert!(r.abs_diff(64) <= 1 && r == g);
+    assert_eq!(a, 255);
+
+    let levels = Levels {
+        input_black: 50.0,
+        input_white: 200.0,
+        ..Levels::default()
+    };
+    let expected = Operation::Levels(levels).apply(ramp()).unwrap();
+    assert_eq!(pixel(&expected, 110)[0], 102);
+}